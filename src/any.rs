@@ -0,0 +1,330 @@
+use crate::{
+    arguments::DuckDBArguments,
+    column::DuckDBColumn,
+    connection::{DuckDBConnection, QueryResultType},
+    database::DuckDB,
+    options::DuckDBConnectOptions,
+    query_result::DuckDBQueryResult,
+    row::DuckDBRow,
+    transaction::DuckDBTransactionManager,
+    type_info::{DuckDBType, DuckdbDBTypeInfo},
+    value::{DuckDBValue, DuckDBValueData},
+};
+use futures::{
+    StreamExt,
+    future::BoxFuture,
+    stream::{self, BoxStream, TryStreamExt},
+};
+use sqlx_core::{
+    Either, Error, Result,
+    any::{
+        Any, AnyArguments, AnyColumn, AnyConnectOptions, AnyConnectionBackend, AnyQueryResult,
+        AnyRow, AnyStatement, AnyTypeInfo, AnyTypeInfoKind, AnyValue, AnyValueKind,
+    },
+    connection::{ConnectOptions, Connection},
+    database::Database,
+    describe::Describe,
+    transaction::TransactionManager,
+};
+
+// Driver to install with `sqlx::any::install_drivers`. Any parses the connection string as a url
+// before handing it to DuckDB, so `:memory:` must be percent encoded: `duckdb://%3Amemory%3A`.
+sqlx_core::declare_driver_with_optional_migrate!(DRIVER = DuckDB);
+
+impl AnyConnectionBackend for DuckDBConnection {
+    fn name(&self) -> &str {
+        <DuckDB as Database>::NAME
+    }
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, Result<()>> {
+        Connection::close(*self)
+    }
+
+    fn close_hard(self: Box<Self>) -> BoxFuture<'static, Result<()>> {
+        Connection::close_hard(*self)
+    }
+
+    fn ping(&mut self) -> BoxFuture<'_, Result<()>> {
+        Connection::ping(self)
+    }
+
+    fn begin(&mut self) -> BoxFuture<'_, Result<()>> {
+        DuckDBTransactionManager::begin(self)
+    }
+
+    fn commit(&mut self) -> BoxFuture<'_, Result<()>> {
+        DuckDBTransactionManager::commit(self)
+    }
+
+    fn rollback(&mut self) -> BoxFuture<'_, Result<()>> {
+        DuckDBTransactionManager::rollback(self)
+    }
+
+    fn start_rollback(&mut self) {
+        DuckDBTransactionManager::start_rollback(self)
+    }
+
+    fn shrink_buffers(&mut self) {
+        Connection::shrink_buffers(self)
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, Result<()>> {
+        Connection::flush(self)
+    }
+
+    fn should_flush(&self) -> bool {
+        Connection::should_flush(self)
+    }
+
+    #[cfg(feature = "migrate")]
+    fn as_migrate(&mut self) -> Result<&mut (dyn sqlx_core::migrate::Migrate + Send + 'static)> {
+        Ok(self)
+    }
+
+    fn fetch_many<'q>(
+        &'q mut self,
+        query: &'q str,
+        persistent: bool,
+        arguments: Option<AnyArguments<'q>>,
+    ) -> BoxStream<'q, Result<Either<AnyQueryResult, AnyRow>>> {
+        let arguments = match arguments.map(map_arguments).transpose() {
+            Ok(arguments) => arguments,
+            Err(error) => return stream::once(async { Err(error) }).boxed(),
+        };
+        self.run(QueryResultType::Many, query, arguments, persistent)
+            .map(|message| match message? {
+                Either::Left(result) => Ok(Either::Left(map_result(result))),
+                Either::Right(row) => Ok(Either::Right(AnyRow::try_from(&row)?)),
+            })
+            .boxed()
+    }
+
+    fn fetch_optional<'q>(
+        &'q mut self,
+        query: &'q str,
+        persistent: bool,
+        arguments: Option<AnyArguments<'q>>,
+    ) -> BoxFuture<'q, Result<Option<AnyRow>>> {
+        let arguments = match arguments.map(map_arguments).transpose() {
+            Ok(arguments) => arguments,
+            Err(error) => return Box::pin(async { Err(error) }),
+        };
+        let mut stream = self.run(QueryResultType::One, query, arguments, persistent);
+        Box::pin(async move {
+            // The results of the statements preceding the first row are skipped
+            while let Some(message) = stream.try_next().await? {
+                if let Either::Right(row) = message {
+                    return Ok(Some(AnyRow::try_from(&row)?));
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    fn prepare_with<'c, 'q: 'c>(
+        &'c mut self,
+        _sql: &'q str,
+        _parameters: &[AnyTypeInfo],
+    ) -> BoxFuture<'c, Result<AnyStatement<'q>>> {
+        Box::pin(async { Err(unsupported_operation("prepared statements")) })
+    }
+
+    fn describe<'q>(&'q mut self, _sql: &'q str) -> BoxFuture<'q, Result<Describe<Any>>> {
+        Box::pin(async { Err(unsupported_operation("describing queries")) })
+    }
+}
+
+/// Kind of the Any value a DuckDB type is mapped to, the integers are widened to the smallest
+/// Any integer able to hold all their values.
+fn map_kind(data_type: &DuckDBType) -> Option<AnyTypeInfoKind> {
+    type T = DuckDBType;
+    let kind = match data_type {
+        T::Null => AnyTypeInfoKind::Null,
+        T::Boolean => AnyTypeInfoKind::Bool,
+        T::Int8 | T::UInt8 | T::Int16 => AnyTypeInfoKind::SmallInt,
+        T::UInt16 | T::Int32 => AnyTypeInfoKind::Integer,
+        T::UInt32 | T::Int64 => AnyTypeInfoKind::BigInt,
+        T::Float32 => AnyTypeInfoKind::Real,
+        T::Float64 => AnyTypeInfoKind::Double,
+        T::Varchar | T::Json => AnyTypeInfoKind::Text,
+        T::Blob => AnyTypeInfoKind::Blob,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn map_value(
+    data: &DuckDBValueData,
+    type_info: &DuckdbDBTypeInfo,
+) -> Result<AnyValueKind<'static>> {
+    type V = DuckDBValueData;
+    let value = match data {
+        V::Boolean(v) => AnyValueKind::Bool(*v),
+        V::Int8(v) => AnyValueKind::SmallInt((*v).into()),
+        V::UInt8(v) => AnyValueKind::SmallInt((*v).into()),
+        V::Int16(v) => AnyValueKind::SmallInt(*v),
+        V::UInt16(v) => AnyValueKind::Integer((*v).into()),
+        V::Int32(v) => AnyValueKind::Integer(*v),
+        V::UInt32(v) => AnyValueKind::BigInt((*v).into()),
+        V::Int64(v) => AnyValueKind::BigInt(*v),
+        V::Float32(v) => AnyValueKind::Real(*v),
+        V::Float64(v) => AnyValueKind::Double(*v),
+        V::Varchar(v) | V::Json(v) => AnyValueKind::Text(v.clone().into()),
+        V::Blob(v) => AnyValueKind::Blob(v.to_vec().into()),
+        V::Null => AnyValueKind::Null(AnyTypeInfo::try_from(type_info)?.kind),
+        _ => return Err(unsupported_type(type_info)),
+    };
+    Ok(value)
+}
+
+fn unsupported_type(type_info: &DuckdbDBTypeInfo) -> Error {
+    Error::AnyDriverError(
+        format!("Any driver does not support the DuckDB type {}", type_info).into(),
+    )
+}
+
+fn unsupported_operation(operation: &str) -> Error {
+    Error::AnyDriverError(format!("DuckDB driver does not support {operation} yet").into())
+}
+
+impl TryFrom<&DuckdbDBTypeInfo> for AnyTypeInfo {
+    type Error = Error;
+
+    fn try_from(type_info: &DuckdbDBTypeInfo) -> Result<Self, Self::Error> {
+        Ok(AnyTypeInfo {
+            kind: map_kind(&type_info.data_type).ok_or_else(|| unsupported_type(type_info))?,
+        })
+    }
+}
+
+impl TryFrom<&DuckDBColumn> for AnyColumn {
+    type Error = Error;
+
+    fn try_from(column: &DuckDBColumn) -> Result<Self, Self::Error> {
+        let type_info =
+            AnyTypeInfo::try_from(&column.type_info).map_err(|e| Error::ColumnDecode {
+                index: column.name.to_string(),
+                source: e.into(),
+            })?;
+        Ok(AnyColumn {
+            ordinal: column.ordinal,
+            name: column.name.clone(),
+            type_info,
+        })
+    }
+}
+
+impl TryFrom<&DuckDBRow> for AnyRow {
+    type Error = Error;
+
+    fn try_from(row: &DuckDBRow) -> Result<Self, Self::Error> {
+        let columns = row
+            .columns
+            .iter()
+            .map(AnyColumn::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let values = row
+            .values
+            .iter()
+            .zip(row.columns.iter())
+            .map(|(value, column)| {
+                map_value(value, &column.type_info)
+                    .map(|kind| AnyValue { kind })
+                    .map_err(|e| Error::ColumnDecode {
+                        index: column.name.to_string(),
+                        source: e.into(),
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(AnyRow {
+            column_names: row.column_names.clone(),
+            columns,
+            values,
+        })
+    }
+}
+
+impl TryFrom<&AnyConnectOptions> for DuckDBConnectOptions {
+    type Error = Error;
+
+    fn try_from(options: &AnyConnectOptions) -> Result<Self, Self::Error> {
+        // DuckDB does not log the statements, the log settings are ignored
+        DuckDBConnectOptions::from_url(&options.database_url)
+    }
+}
+
+fn map_arguments(arguments: AnyArguments<'_>) -> Result<DuckDBArguments> {
+    Ok(DuckDBArguments {
+        values: arguments
+            .values
+            .0
+            .into_iter()
+            .map(|value| {
+                Ok(match value {
+                    AnyValueKind::Null(kind) => DuckDBValue {
+                        data: DuckDBValueData::Null,
+                        type_info: match kind {
+                            AnyTypeInfoKind::Bool => DuckDBType::Boolean,
+                            AnyTypeInfoKind::SmallInt => DuckDBType::Int16,
+                            AnyTypeInfoKind::Integer => DuckDBType::Int32,
+                            AnyTypeInfoKind::BigInt => DuckDBType::Int64,
+                            AnyTypeInfoKind::Real => DuckDBType::Float32,
+                            AnyTypeInfoKind::Double => DuckDBType::Float64,
+                            AnyTypeInfoKind::Text => DuckDBType::Varchar,
+                            AnyTypeInfoKind::Blob => DuckDBType::Blob,
+                            AnyTypeInfoKind::Null => DuckDBType::Null,
+                        }
+                        .into(),
+                    },
+                    AnyValueKind::Bool(v) => {
+                        argument(DuckDBType::Boolean, DuckDBValueData::Boolean(v))
+                    }
+                    AnyValueKind::SmallInt(v) => {
+                        argument(DuckDBType::Int16, DuckDBValueData::Int16(v))
+                    }
+                    AnyValueKind::Integer(v) => {
+                        argument(DuckDBType::Int32, DuckDBValueData::Int32(v))
+                    }
+                    AnyValueKind::BigInt(v) => {
+                        argument(DuckDBType::Int64, DuckDBValueData::Int64(v))
+                    }
+                    AnyValueKind::Real(v) => {
+                        argument(DuckDBType::Float32, DuckDBValueData::Float32(v))
+                    }
+                    AnyValueKind::Double(v) => {
+                        argument(DuckDBType::Float64, DuckDBValueData::Float64(v))
+                    }
+                    AnyValueKind::Text(v) => argument(
+                        DuckDBType::Varchar,
+                        DuckDBValueData::Varchar(v.into_owned()),
+                    ),
+                    AnyValueKind::Blob(v) => argument(
+                        DuckDBType::Blob,
+                        DuckDBValueData::Blob(v.into_owned().into()),
+                    ),
+                    // AnyValueKind is `#[non_exhaustive]`, kinds added later are not supported
+                    _ => {
+                        return Err(Error::AnyDriverError(
+                            format!("DuckDB driver does not support the Any value {value:?}")
+                                .into(),
+                        ));
+                    }
+                })
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn argument(data_type: DuckDBType, data: DuckDBValueData) -> DuckDBValue {
+    DuckDBValue {
+        data,
+        type_info: data_type.into(),
+    }
+}
+
+fn map_result(result: DuckDBQueryResult) -> AnyQueryResult {
+    AnyQueryResult {
+        rows_affected: result.rows_affected,
+        last_insert_id: result.last_insert_id,
+    }
+}
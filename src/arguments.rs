@@ -1,15 +1,86 @@
-use crate::{database::DuckDB, type_info::DuckDBField};
+use crate::{
+    database::DuckDB,
+    error::DuckDBError,
+    value::{DuckDBValue, DuckDBValueData},
+};
+use libduckdb_sys::*;
 use sqlx_core::{
-    arguments::Arguments, encode::Encode, error::BoxDynError, impl_into_arguments_for_arguments,
+    Error, Result,
+    arguments::Arguments,
+    encode::{Encode, IsNull},
+    error::BoxDynError,
+    impl_encode_for_option, impl_into_arguments_for_arguments,
     types::Type,
 };
 
 #[derive(Default)]
 pub struct DuckDBArguments {
-    pub(crate) values: Vec<DuckDBField>,
+    pub(crate) values: Vec<DuckDBValue>,
 }
 
-impl DuckDBArguments {}
+/// Binds `values` to the parameters of a prepared statement, in order.
+pub(crate) unsafe fn bind_arguments(
+    statement: duckdb_prepared_statement,
+    values: &[DuckDBValue],
+) -> Result<()> {
+    type V = DuckDBValueData;
+    for (i, value) in values.iter().enumerate() {
+        let index = (i + 1) as idx_t;
+        let rc = unsafe {
+            match &value.data {
+                V::Null => duckdb_bind_null(statement, index),
+                V::Boolean(v) => duckdb_bind_boolean(statement, index, *v),
+                V::Int8(v) => duckdb_bind_int8(statement, index, *v),
+                V::Int16(v) => duckdb_bind_int16(statement, index, *v),
+                V::Int32(v) => duckdb_bind_int32(statement, index, *v),
+                V::Int64(v) => duckdb_bind_int64(statement, index, *v),
+                V::Int128(v) => {
+                    let value = duckdb_hugeint {
+                        lower: *v as u64,
+                        upper: (*v >> 64) as i64,
+                    };
+                    duckdb_bind_hugeint(statement, index, value)
+                }
+                V::UInt8(v) => duckdb_bind_uint8(statement, index, *v),
+                V::UInt16(v) => duckdb_bind_uint16(statement, index, *v),
+                V::UInt32(v) => duckdb_bind_uint32(statement, index, *v),
+                V::UInt64(v) => duckdb_bind_uint64(statement, index, *v),
+                V::UInt128(v) => {
+                    let value = duckdb_uhugeint {
+                        lower: *v as u64,
+                        upper: (*v >> 64) as u64,
+                    };
+                    duckdb_bind_uhugeint(statement, index, value)
+                }
+                V::Float32(v) => duckdb_bind_float(statement, index, *v),
+                V::Float64(v) => duckdb_bind_double(statement, index, *v),
+                // DuckDB casts the text into the JSON type where it is expected
+                V::Varchar(v) | V::Json(v) => duckdb_bind_varchar_length(
+                    statement,
+                    index,
+                    v.as_ptr().cast(),
+                    v.len() as idx_t,
+                ),
+                V::Blob(v) => {
+                    duckdb_bind_blob(statement, index, v.as_ptr().cast(), v.len() as idx_t)
+                }
+                data => {
+                    return Err(Error::Encode(
+                        format!("Cannot bind {data:?} of type {}", value.type_info).into(),
+                    ));
+                }
+            }
+        };
+        if rc != duckdb_state_DuckDBSuccess {
+            return Err(DuckDBError::new(format!(
+                "Could not bind argument {index} of type {}",
+                value.type_info
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
 
 impl<'q> Arguments<'q> for DuckDBArguments {
     type Database = DuckDB;
@@ -18,12 +89,17 @@ impl<'q> Arguments<'q> for DuckDBArguments {
         self.values.reserve(size);
     }
 
-    fn add<T>(&mut self, value: T) -> Result<(), BoxDynError>
+    fn add<T>(&mut self, value: T) -> std::result::Result<(), BoxDynError>
     where
         T: 'q + Encode<'q, Self::Database> + Type<Self::Database>,
     {
         let type_info = value.produces().unwrap_or_else(T::type_info);
-        self.values.push(type_info.into());
+        let mut buffer = DuckDBArgumentBuffer::default();
+        let data = match value.encode(&mut buffer)? {
+            IsNull::Yes => DuckDBValueData::Null,
+            IsNull::No => buffer.data,
+        };
+        self.values.push(DuckDBValue { data, type_info });
         Ok(())
     }
 
@@ -32,6 +108,11 @@ impl<'q> Arguments<'q> for DuckDBArguments {
     }
 }
 
-pub struct DuckDBArgumentBuffer;
+/// Receives the value of an argument being encoded.
+#[derive(Default)]
+pub struct DuckDBArgumentBuffer {
+    pub(crate) data: DuckDBValueData,
+}
 
 impl_into_arguments_for_arguments!(DuckDBArguments);
+impl_encode_for_option!(DuckDB);
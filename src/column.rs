@@ -1,4 +1,4 @@
-use crate::{database::DuckDB, type_info::DuckdbDBTypeInfo, value::DuckDBValueRef};
+use crate::{database::DuckDB, type_info::DuckdbDBTypeInfo};
 use sqlx_core::{column::Column, ext::ustr::UStr};
 
 #[derive(Debug)]
@@ -8,14 +8,6 @@ pub struct DuckDBColumn {
     pub(crate) type_info: DuckdbDBTypeInfo,
 }
 
-impl DuckDBColumn {
-    pub fn value_ref(&self) -> DuckDBValueRef<'_> {
-        DuckDBValueRef {
-            type_info: &self.type_info,
-        }
-    }
-}
-
 impl Column for DuckDBColumn {
     type Database = DuckDB;
 
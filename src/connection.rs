@@ -1,10 +1,19 @@
-use crate::arguments::DuckDBArguments;
+use crate::arguments::{DuckDBArguments, bind_arguments};
 use crate::cbox::CBox;
 use crate::column::DuckDBColumn;
-use crate::extract_value::extract_value;
+use crate::extract_value::{DuckDBVector, extract_value};
+use crate::function::{self, DuckDBAggregate, DuckDBChunkColumn, IntoDuckDBValue};
+use crate::interrupt::{DuckDBInterruptHandle, InterruptOnDrop};
+use crate::progress::DuckDBProgressHandle;
 use crate::query_result::DuckDBQueryResult;
 use crate::row::DuckDBRow;
-use crate::{database::DuckDB, error::DuckDBError, options::DuckDBConnectOptions};
+use crate::table_function::{self, DuckDBTableFunction, IntoDuckDBRow, IteratorTableFunction};
+use crate::type_info::DuckdbDBTypeInfo;
+use crate::{
+    database::{ConnectionHandle, DuckDB, DuckDBDatabase},
+    error::DuckDBError,
+    options::{DuckDBConnectOptions, Extension, make_cstring},
+};
 use futures::future::BoxFuture;
 use futures::prelude::stream;
 use futures::stream::BoxStream;
@@ -12,23 +21,23 @@ use futures::{FutureExt, Stream, StreamExt};
 use libduckdb_sys::*;
 use sqlx_core::rt::spawn_blocking;
 use sqlx_core::{
-    Either, Error, Result,
+    Either, Error, HashMap, Result,
+    acquire::Acquire,
     connection::Connection,
     database::Database,
     describe::Describe,
+    error::BoxDynError,
     executor::{Execute, Executor},
+    ext::ustr::UStr,
     transaction::Transaction,
 };
-use std::ffi::{CStr, CString, c_char};
-use std::ops::DerefMut;
+use std::ffi::{CStr, CString};
 use std::pin::Pin;
-use std::ptr::null_mut;
-use std::sync::LazyLock;
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Arc;
 use std::{future, mem, ptr};
 
-#[derive(PartialEq)]
-enum QueryResultType {
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum QueryResultType {
     Many,
     One,
     None,
@@ -40,133 +49,291 @@ enum QueryResultType {
 /// to allow non-blocking access to the database.
 ///
 /// Dropping this struct will not close the database because open databases are cached, to avoid
-/// opening the same database multiple times. Most likely the database will be closed at the program
-/// end.
+/// opening the same database multiple times, see [DuckDBDatabase] to control when it is closed.
+///
+/// Dropping the stream or future of a running query interrupts it, queries can also be interrupted
+/// from other tasks using [DuckDBConnection::interrupt_handle].
 #[derive(Debug)]
 pub struct DuckDBConnection {
-    pub(crate) connection: CBox<duckdb_connection>,
+    pub(crate) connection: Arc<ConnectionHandle>,
     pub(crate) transaction: bool,
+    pub(crate) row_buffer_size: usize,
+    /// Held by the blocking tasks using the connection, closing waits for all of them to end.
+    task_token: flume::Sender<()>,
+    tasks_ended: flume::Receiver<()>,
+    /// Lock held while the migrations run.
+    #[cfg(feature = "migrate")]
+    pub(crate) migration_lock: Option<std::fs::File>,
 }
 
 impl DuckDBConnection {
-    pub(crate) fn duckdb_instance_cache() -> &'static AtomicPtr<_duckdb_instance_cache> {
-        static DATABASE_CACHE: LazyLock<CBox<AtomicPtr<_duckdb_instance_cache>>> =
-            LazyLock::new(|| {
-                CBox::new(
-                    AtomicPtr::new(unsafe { duckdb_create_instance_cache() }),
-                    |ptr| unsafe {
-                        duckdb_destroy_instance_cache(&mut ptr.load(Ordering::Relaxed))
-                    },
-                )
-            });
-        &**DATABASE_CACHE
+    pub(crate) async fn establish(options: &DuckDBConnectOptions) -> Result<Self> {
+        // Validated before opening the database to leave no instance behind on error
+        options.connection_init_sql()?;
+        let database = DuckDBDatabase::open(options).await?;
+        Self::open(&database, options).await
     }
 
-    pub(crate) async fn establish(options: &DuckDBConnectOptions) -> Result<Self> {
-        let db_cache = DuckDBConnection::duckdb_instance_cache().load(Ordering::Relaxed);
-        let config = options.create_duckdb_config()?;
-        let mut database: duckdb_database = null_mut();
-        let connection = unsafe {
-            let mut error: *mut c_char = null_mut();
-            if duckdb_get_or_create_from_cache(
-                db_cache,
-                options.path.as_ptr(),
-                &mut database,
-                *config,
-                &mut error,
-            ) != duckdb_state_DuckDBSuccess
-            {
-                return Err(Error::Configuration(
-                    format!(
-                        "Error while opening a (possibly cached) database instance: `{}`",
-                        CStr::from_ptr(error).to_str().unwrap()
-                    )
-                    .into(),
-                ));
+    /// Opens a new connection to the database, configured according to the connection-level
+    /// options (like the session settings), the database-level ones are ignored.
+    pub(crate) async fn open(
+        database: &DuckDBDatabase,
+        options: &DuckDBConnectOptions,
+    ) -> Result<Self> {
+        let init_sql = options.connection_init_sql()?;
+        let connection = Arc::new(ConnectionHandle::new(database)?);
+        for extension in &options.extensions {
+            unsafe { Self::load_extension(**connection, extension)? };
+        }
+        if options.track_progress {
+            // DuckDB computes the progress only when the progress bar is enabled
+            unsafe {
+                Self::execute_raw(
+                    **connection,
+                    c"SET enable_progress_bar = true; SET enable_progress_bar_print = false;",
+                )?;
             }
-            let mut connection: duckdb_connection = null_mut();
-            if duckdb_connect(database, &mut connection) != duckdb_state_DuckDBSuccess {
-                return Err(Error::Configuration(
-                    format!(
-                        "Error connecting to the database `{}`",
-                        CStr::from_ptr(error).to_str().unwrap()
-                    )
-                    .into(),
-                ));
+        }
+        if let Some(sql) = init_sql {
+            unsafe {
+                Self::execute_raw(**connection, &sql)?;
             }
-            CBox::new(connection, |mut connection| {
-                duckdb_disconnect(&mut connection);
-            })
-        };
+        }
+        let (task_token, tasks_ended) = flume::bounded(0);
         Ok(DuckDBConnection {
             connection,
             transaction: false,
+            row_buffer_size: options.row_buffer_size,
+            task_token,
+            tasks_ended,
+            #[cfg(feature = "migrate")]
+            migration_lock: None,
         })
     }
 
-    pub(crate) fn run<'q, E: Execute<'q, DuckDB>>(
+    /// Returns the database this connection is connected to.
+    pub fn database(&self) -> &DuckDBDatabase {
+        self.connection.database()
+    }
+
+    /// Returns a handle that can interrupt the queries running on this connection from other tasks.
+    pub fn interrupt_handle(&self) -> DuckDBInterruptHandle {
+        DuckDBInterruptHandle::new(&self.connection)
+    }
+
+    /// Returns a handle that can observe the progress of the queries running on this connection
+    /// from other tasks.
+    pub fn progress_handle(&self) -> DuckDBProgressHandle {
+        DuckDBProgressHandle::new(&self.connection)
+    }
+
+    /// Registers a scalar function implemented in Rust, callable from SQL on every connection to
+    /// the same database.
+    ///
+    /// The function is vectorized: it receives one [DuckDBChunkColumn] for each parameter and
+    /// returns a value for each row of the chunk. A function without parameters is called for each
+    /// row and returns a single value. The parameter types are given by `parameters`, the return
+    /// type is the one of `R`.
+    pub fn register_scalar_function<R, F>(
         &mut self,
-        result_type: QueryResultType,
-        query: E,
-        arguments: Option<DuckDBArguments>,
-        cached: bool,
-    ) -> Pin<Box<dyn Stream<Item = Result<Either<DuckDBQueryResult, DuckDBRow>>> + Send>> {
-        let query = CString::new(query.sql()).map_err(|e| DuckDBError::new(e.to_string()));
-        if query.is_err() {
-            return stream::once(future::ready(Err(query.unwrap_err().into()))).boxed();
-        }
-        let query = query.unwrap();
-        let (tx, rx) = if matches!(result_type, QueryResultType::Many) {
-            flume::unbounded()
-        } else {
-            flume::bounded(1)
+        name: &str,
+        parameters: &[DuckdbDBTypeInfo],
+        function: F,
+    ) -> Result<()>
+    where
+        R: IntoDuckDBValue,
+        F: Fn(&[DuckDBChunkColumn]) -> Result<Vec<R>, BoxDynError> + Send + Sync + 'static,
+    {
+        function::register_scalar_function(**self.connection, name, parameters, function)
+    }
+
+    /// Registers an aggregate function implemented in Rust, callable from SQL on every connection
+    /// to the same database.
+    ///
+    /// The parameter types are given by `parameters`, the return type is the one of
+    /// [DuckDBAggregate::Output].
+    pub fn register_aggregate_function<A: DuckDBAggregate>(
+        &mut self,
+        name: &str,
+        parameters: &[DuckdbDBTypeInfo],
+        aggregate: A,
+    ) -> Result<()> {
+        function::register_aggregate_function(**self.connection, name, parameters, aggregate)
+    }
+
+    /// Registers a table function implemented in Rust, callable from the `FROM` clause on every
+    /// connection to the same database.
+    ///
+    /// The positional parameters are given by `parameters`, the named ones by `named_parameters`.
+    pub fn register_table_function<T: DuckDBTableFunction>(
+        &mut self,
+        name: &str,
+        parameters: &[DuckdbDBTypeInfo],
+        named_parameters: &[(&str, DuckdbDBTypeInfo)],
+        function: T,
+    ) -> Result<()> {
+        table_function::register_table_function(
+            **self.connection,
+            name,
+            parameters,
+            named_parameters,
+            function,
+        )
+    }
+
+    /// Registers a table function without parameters, each scan of `name()` consumes a new
+    /// iterator created by `iterator`.
+    ///
+    /// Each item is a row, `columns` are the names of its values.
+    pub fn register_table_iterator<I, F>(
+        &mut self,
+        name: &str,
+        columns: &[&str],
+        iterator: F,
+    ) -> Result<()>
+    where
+        F: Fn() -> I + Send + Sync + 'static,
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: IntoDuckDBRow,
+    {
+        let function = IteratorTableFunction {
+            columns: columns.iter().map(|v| v.to_string()).collect(),
+            iterator,
         };
-        let connection = AtomicPtr::new(*self.connection);
-        spawn_blocking(move || unsafe {
-            let mut prepared_statement =
-                CBox::new(ptr::null_mut(), |mut ptr| duckdb_destroy_prepare(&mut ptr));
-            let rc = duckdb_prepare(
-                connection.load(Ordering::Relaxed),
-                query.as_ptr(),
-                &mut *prepared_statement,
-            );
+        self.register_table_function(name, &[], &[], function)
+    }
+
+    /// Loads an extension, installing it first when it is not available locally.
+    unsafe fn load_extension(connection: duckdb_connection, extension: &Extension) -> Result<()> {
+        let name = extension.name();
+        let literal = format!("'{}'", name.replace('\'', "''"));
+        let load = make_cstring(&format!("LOAD {literal}"))?;
+        let mut result = unsafe { Self::execute_raw(connection, &load) };
+        if result.is_err() && matches!(extension, Extension::Named(_)) {
+            let install = make_cstring(&format!("INSTALL {literal}; LOAD {literal}"))?;
+            result = unsafe { Self::execute_raw(connection, &install) };
+        }
+        result.map_err(|e| {
+            Error::Configuration(
+                format!("Error while loading the DuckDB extension `{name}`: {e}").into(),
+            )
+        })
+    }
+
+    /// Closes the connection once the blocking tasks still using it, like the queries whose stream
+    /// was dropped before the end, release it.
+    async fn disconnect(self) -> Result<()> {
+        let DuckDBConnection {
+            connection,
+            task_token,
+            tasks_ended,
+            ..
+        } = self;
+        drop(task_token);
+        // Fails once every token was dropped, nothing is ever sent
+        let _ = tasks_ended.recv_async().await;
+        // Disconnecting can wait for a running query, this is why it happens in a blocking task
+        spawn_blocking(move || drop(connection)).await;
+        Ok(())
+    }
+
+    /// Executes `sql` on the current thread, for the cases where the query cannot be awaited.
+    pub(crate) fn execute_blocking(&self, sql: &CStr) -> Result<()> {
+        unsafe { Self::execute_raw(**self.connection, sql) }
+    }
+
+    unsafe fn execute_raw(connection: duckdb_connection, sql: &CStr) -> Result<()> {
+        unsafe {
+            let mut result: duckdb_result = mem::zeroed();
+            let rc = duckdb_query(connection, sql.as_ptr(), &mut result);
+            let mut result = CBox::new(result, |mut r| duckdb_destroy_result(&mut r));
             if rc != duckdb_state_DuckDBSuccess {
-                let message = CStr::from_ptr(duckdb_prepare_error(*prepared_statement))
+                return Err(DuckDBError::from_result(&mut result)
+                    .unwrap_or_else(|| DuckDBError::new("Error while executing the query".into()))
+                    .into());
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn prepare_error(prepared_statement: duckdb_prepared_statement) -> DuckDBError {
+        let message = unsafe { duckdb_prepare_error(prepared_statement) };
+        if message.is_null() {
+            DuckDBError::new("Error while preparing the query".into())
+        } else {
+            DuckDBError::from_duckdb_message(
+                unsafe { CStr::from_ptr(message) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Column metadata of a result, read from the vectors of its first chunk.
+    unsafe fn result_columns(
+        result: &mut duckdb_result,
+        info: &[DuckDBVector],
+    ) -> Result<(Arc<Vec<DuckDBColumn>>, Arc<HashMap<UStr, usize>>)> {
+        let mut columns = Vec::with_capacity(info.len());
+        let mut column_names = HashMap::with_capacity(info.len());
+        for (ordinal, info) in info.iter().enumerate() {
+            let name: UStr =
+                unsafe { CStr::from_ptr(duckdb_column_name(result, ordinal as idx_t)) }
                     .to_str()
                     .unwrap()
+                    .to_owned()
                     .into();
-                let _ = tx.send(Err(DuckDBError::new(message).into()));
-            }
+            let type_info = DuckdbDBTypeInfo::from_logical_type(info.logical_type())?;
+            // With duplicate names, the first column is the one found by name
+            column_names.entry(name.clone()).or_insert(ordinal);
+            columns.push(DuckDBColumn {
+                name,
+                ordinal,
+                type_info,
+            });
+        }
+        Ok((Arc::new(columns), Arc::new(column_names)))
+    }
+
+    /// Executes a prepared statement, sending its rows followed by its result. Returns `false`
+    /// when the execution must stop, because of an error, because the stream was dropped or
+    /// because the requested row was sent.
+    unsafe fn run_statement(
+        prepared_statement: duckdb_prepared_statement,
+        result_type: QueryResultType,
+        tx: &flume::Sender<Result<Either<DuckDBQueryResult, DuckDBRow>>>,
+    ) -> bool {
+        unsafe {
             let mut result: duckdb_result = mem::zeroed();
             let rc = if matches!(result_type, QueryResultType::Many) {
-                duckdb_execute_prepared_streaming(*prepared_statement, &mut result)
+                duckdb_execute_prepared_streaming(prepared_statement, &mut result)
             } else {
-                duckdb_execute_prepared(*prepared_statement, &mut result)
+                duckdb_execute_prepared(prepared_statement, &mut result)
             };
             let mut result = CBox::new(result, |mut r| duckdb_destroy_result(&mut r));
             if rc != duckdb_state_DuckDBSuccess {
-                let _ = tx.send(Err(DuckDBError::new(
-                    "Error while executing the query".into(),
-                )
-                .into()));
+                let error = DuckDBError::from_result(&mut result)
+                    .unwrap_or_else(|| DuckDBError::new("Error while executing the query".into()));
+                let _ = tx.send(Err(error.into()));
+                return false;
             }
-            let statement_type = duckdb_result_statement_type(*result);
+            // Statements not returning rows produce a result with the number of changed rows
             if result_type == QueryResultType::None
-                || matches!(
-                    statement_type,
-                    duckdb_statement_type_DUCKDB_STATEMENT_TYPE_INSERT
-                        | duckdb_statement_type_DUCKDB_STATEMENT_TYPE_DELETE
-                )
+                || duckdb_result_return_type(*result)
+                    != duckdb_result_type_DUCKDB_RESULT_TYPE_QUERY_RESULT
             {
                 let rows_affected = duckdb_rows_changed(&mut *result);
                 let _ = tx.send(Ok(sqlx_core::Either::Left(DuckDBQueryResult {
                     rows_affected,
                     ..Default::default()
                 })));
-                return;
+                return true;
             }
             // duckdb_execute_prepared_streaming can also produce non streaming result, must check separately
             let is_streaming = duckdb_result_is_streaming(*result);
+            // Shared by all the rows, read from the first chunk
+            let mut columns = None;
             loop {
                 let chunk = CBox::new(
                     if is_streaming {
@@ -177,54 +344,161 @@ impl DuckDBConnection {
                     |mut v| duckdb_destroy_data_chunk(&mut v),
                 );
                 if chunk.is_null() {
-                    return;
+                    // Streaming results report errors happening while fetching
+                    if let Some(error) = DuckDBError::from_result(&mut result) {
+                        let _ = tx.send(Err(error.into()));
+                        return false;
+                    }
+                    return tx
+                        .send(Ok(sqlx_core::Either::Left(DuckDBQueryResult::default())))
+                        .is_ok();
                 }
                 let rows = if result_type == QueryResultType::One {
-                    1
+                    duckdb_data_chunk_get_size(*chunk).min(1)
                 } else {
                     duckdb_data_chunk_get_size(*chunk)
                 };
                 let cols = duckdb_data_chunk_get_column_count(*chunk);
                 let info = (0..cols)
-                    .map(|i| {
-                        let vector = duckdb_data_chunk_get_vector(*chunk, i);
-                        let logical_type = duckdb_vector_get_column_type(vector);
-                        let type_id = duckdb_get_type_id(logical_type);
-                        let data = duckdb_vector_get_data(vector);
-                        let validity = duckdb_vector_get_validity(vector);
-                        let name = CStr::from_ptr(duckdb_column_name(result.deref_mut(), i))
-                            .to_str()
-                            .unwrap();
-                        (vector, logical_type, type_id, data, validity, name)
-                    })
+                    .map(|i| DuckDBVector::new(duckdb_data_chunk_get_vector(*chunk, i)))
                     .collect::<Box<[_]>>();
-                (0..rows).for_each(|row| {
-                    let columns = (0..cols).map(|col| {
-                        let col = col as usize;
-                        let info = info[col];
-                        Ok(DuckDBColumn {
-                            name: info.5.into(),
-                            ordinal: col as usize,
-                            type_info: extract_value(
-                                info.0,
-                                row as usize,
-                                info.1,
-                                info.2,
-                                info.3,
-                                info.4,
-                            )?
-                            .into(),
+                if columns.is_none() {
+                    match Self::result_columns(&mut result, &info) {
+                        Ok(result_columns) => columns = Some(result_columns),
+                        Err(error) => {
+                            let _ = tx.send(Err(error));
+                            return false;
+                        }
+                    }
+                }
+                let (columns, column_names) = columns.as_ref().unwrap();
+                for row in 0..rows {
+                    let values = info.iter().map(|info| extract_value(info, row as usize));
+                    let message = values.collect::<Result<_>>().map(|values| {
+                        sqlx_core::Either::Right(DuckDBRow {
+                            values,
+                            columns: columns.clone(),
+                            column_names: column_names.clone(),
                         })
                     });
-                    let message = DuckDBRow(columns.collect::<Result<_>>().unwrap());
-                    let _ = tx.send(Ok(sqlx_core::Either::Right(message)));
-                });
-                if result_type == QueryResultType::One {
+                    let failed = message.is_err();
+                    if tx.send(message).is_err() || failed {
+                        // Either the stream was dropped or the row could not be read, no point
+                        // in fetching the remaining rows
+                        return false;
+                    }
+                }
+                if result_type == QueryResultType::One && rows > 0 {
+                    return false;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn run<'q, E: Execute<'q, DuckDB>>(
+        &mut self,
+        result_type: QueryResultType,
+        query: E,
+        arguments: Option<DuckDBArguments>,
+        cached: bool,
+    ) -> Pin<Box<dyn Stream<Item = Result<Either<DuckDBQueryResult, DuckDBRow>>> + Send>> {
+        let query = CString::new(query.sql()).map_err(|e| DuckDBError::new(e.to_string()));
+        if query.is_err() {
+            return stream::once(future::ready(Err(query.unwrap_err().into()))).boxed();
+        }
+        let query = query.unwrap();
+        // The blocking task waits on the bounded channel when the consumer is slower
+        let (tx, rx) = if matches!(result_type, QueryResultType::Many) {
+            flume::bounded(self.row_buffer_size)
+        } else {
+            flume::bounded(1)
+        };
+        let connection = self.connection.clone();
+        let task_token = self.task_token.clone();
+        spawn_blocking(move || unsafe {
+            // The connection is released before the stream ends, when the sender is dropped, and
+            // before the token, when the task ends
+            let _task_token = task_token;
+            let tx = tx;
+            let connection = connection;
+            if tx.is_disconnected() {
+                // The stream was dropped before the query started
+                return;
+            }
+            let mut statements = CBox::new(ptr::null_mut(), |mut ptr| {
+                duckdb_destroy_extracted(&mut ptr)
+            });
+            let count = duckdb_extract_statements(**connection, query.as_ptr(), &mut *statements);
+            if count == 0 {
+                let message = duckdb_extract_statements_error(*statements);
+                if message.is_null() {
+                    // Nothing to run, e.g. the query contains only comments
+                    let _ = tx.send(Ok(sqlx_core::Either::Left(DuckDBQueryResult::default())));
+                    return;
+                }
+                // Preparing the whole query reports the parser error with its position
+                let mut prepared_statement =
+                    CBox::new(ptr::null_mut(), |mut ptr| duckdb_destroy_prepare(&mut ptr));
+                let rc = duckdb_prepare(**connection, query.as_ptr(), &mut *prepared_statement);
+                let error = if rc != duckdb_state_DuckDBSuccess {
+                    Self::prepare_error(*prepared_statement)
+                } else {
+                    DuckDBError::from_duckdb_message(
+                        CStr::from_ptr(message).to_string_lossy().into_owned(),
+                    )
+                };
+                let _ = tx.send(Err(error.into()));
+                return;
+            }
+            // The statements run in order, the first failing one stops the execution. Each of
+            // them takes the next arguments, as many as it has parameters.
+            let arguments = arguments.map(|a| a.values).unwrap_or_default();
+            let mut next_argument = 0;
+            for i in 0..count {
+                let mut prepared_statement =
+                    CBox::new(ptr::null_mut(), |mut ptr| duckdb_destroy_prepare(&mut ptr));
+                let rc = duckdb_prepare_extracted_statement(
+                    **connection,
+                    *statements,
+                    i,
+                    &mut *prepared_statement,
+                );
+                if rc != duckdb_state_DuckDBSuccess {
+                    let _ = tx.send(Err(Self::prepare_error(*prepared_statement).into()));
+                    return;
+                }
+                let parameters = duckdb_nparams(*prepared_statement) as usize;
+                let Some(values) = arguments.get(next_argument..next_argument + parameters) else {
+                    let _ = tx.send(Err(Error::Protocol(format!(
+                        "The statement expects {parameters} arguments but {} remain",
+                        arguments.len() - next_argument
+                    ))));
+                    return;
+                };
+                next_argument += parameters;
+                if let Err(error) = bind_arguments(*prepared_statement, values) {
+                    let _ = tx.send(Err(error));
+                    return;
+                }
+                if !Self::run_statement(*prepared_statement, result_type, &tx) {
                     return;
                 }
             }
         });
-        rx.into_stream().boxed()
+        InterruptOnDrop::new(rx.into_stream().boxed(), self.interrupt_handle()).boxed()
+    }
+}
+
+impl<'c> Acquire<'c> for &'c mut DuckDBConnection {
+    type Database = DuckDB;
+    type Connection = &'c mut DuckDBConnection;
+
+    fn acquire(self) -> BoxFuture<'c, Result<Self::Connection>> {
+        Box::pin(future::ready(Ok(self)))
+    }
+
+    fn begin(self) -> BoxFuture<'c, Result<Transaction<'c, DuckDB>>> {
+        Transaction::begin(self)
     }
 }
 
@@ -232,24 +506,24 @@ impl Connection for DuckDBConnection {
     type Database = DuckDB;
     type Options = DuckDBConnectOptions;
 
-    fn close(mut self) -> BoxFuture<'static, Result<(), sqlx_core::Error>> {
-        Box::pin(async move {
-            spawn_blocking(move || unsafe {
-                duckdb_disconnect(self.connection.deref_mut());
-            });
-            Ok(())
-        })
+    fn close(self) -> BoxFuture<'static, Result<(), sqlx_core::Error>> {
+        Box::pin(self.disconnect())
     }
 
     fn close_hard(self) -> BoxFuture<'static, Result<(), sqlx_core::Error>> {
-        Box::pin(async move {
-            drop(self);
-            Ok(())
-        })
+        // The running query would delay the disconnection
+        self.interrupt_handle().interrupt();
+        Box::pin(self.disconnect())
     }
 
     fn ping(&mut self) -> BoxFuture<'_, Result<(), sqlx_core::Error>> {
-        todo!()
+        let connection = self.connection.clone();
+        let task_token = self.task_token.clone();
+        Box::pin(spawn_blocking(move || unsafe {
+            let _task_token = task_token;
+            let connection = connection;
+            Self::execute_raw(**connection, c"SELECT 1")
+        }))
     }
 
     fn begin(
@@ -307,12 +581,13 @@ impl<'c> Executor<'c> for &'c mut DuckDBConnection {
         };
         let mut stream = self.run(QueryResultType::One, query.sql(), arguments, false);
         Box::pin(async move {
-            match stream.next().await {
-                Some(Ok(Either::Right(row))) => Ok(Some(row)),
-                Some(Ok(Either::Left(_))) => Ok(None),
-                Some(Err(e)) => Err(e),
-                None => Ok(None),
+            // The results of the statements preceding the first row are skipped
+            while let Some(message) = stream.next().await {
+                if let Either::Right(row) = message? {
+                    return Ok(Some(row));
+                }
             }
+            Ok(None)
         })
     }
 
@@ -1,15 +1,35 @@
 use crate::{
     arguments::{DuckDBArgumentBuffer, DuckDBArguments},
+    cbox::CBox,
     column::DuckDBColumn,
     connection::DuckDBConnection,
+    options::{DuckDBConnectOptions, InstanceReuse, make_cstring},
     query_result::DuckDBQueryResult,
+    replacement_scan::{self, DuckDBReplacementScan},
     row::DuckDBRow,
     statement::DuckDBStatement,
     transaction::DuckDBTransactionManager,
     type_info::DuckdbDBTypeInfo,
     value::{DuckDBValue, DuckDBValueRef},
 };
-use sqlx_core::database::Database;
+use libduckdb_sys::*;
+use sqlx_core::{
+    Error, Result,
+    database::Database,
+    error::BoxDynError,
+    pool::{Pool, PoolOptions},
+    rt::spawn_blocking,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{CStr, c_char},
+    ops::Deref,
+    ptr::null_mut,
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+    },
+};
 
 /// DuckDB database driver.
 #[derive(Debug)]
@@ -40,3 +60,339 @@ impl Database for DuckDB {
 
     const URL_SCHEMES: &'static [&'static str] = &["duckdb"];
 }
+
+/// Pool of connections to a [DuckDB] database.
+///
+/// The pooled connections share the same database instance, they are health checked with
+/// [Connection::ping](sqlx_core::connection::Connection::ping) before being acquired.
+pub type DuckDBPool = Pool<DuckDB>;
+
+/// Options to configure a [DuckDBPool].
+pub type DuckDBPoolOptions = PoolOptions<DuckDB>;
+
+/// DuckDB instance cache, it guarantees that a database file is opened by a single instance.
+fn duckdb_instance_cache() -> &'static AtomicPtr<_duckdb_instance_cache> {
+    static DATABASE_CACHE: LazyLock<CBox<AtomicPtr<_duckdb_instance_cache>>> =
+        LazyLock::new(|| {
+            CBox::new(
+                AtomicPtr::new(unsafe { duckdb_create_instance_cache() }),
+                |ptr| unsafe { duckdb_destroy_instance_cache(&mut ptr.load(Ordering::Relaxed)) },
+            )
+        });
+    &DATABASE_CACHE
+}
+
+/// Databases kept open by this crate, by instance path.
+fn open_databases() -> &'static Mutex<HashMap<String, DuckDBDatabase>> {
+    static OPEN_DATABASES: LazyLock<Mutex<HashMap<String, DuckDBDatabase>>> =
+        LazyLock::new(Default::default);
+    &OPEN_DATABASES
+}
+
+/// An in-memory database without a name, it is never shared.
+fn is_unnamed_memory(path: &str) -> bool {
+    path.is_empty() || path == ":memory:"
+}
+
+/// Path identifying the database instance in the cache, like DuckDB relative paths are made
+/// absolute while in-memory databases are identified by their name.
+fn instance_path(path: &str) -> String {
+    if path.starts_with(":memory:") {
+        return path.into();
+    }
+    std::path::absolute(path)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.into())
+}
+
+/// Describes the settings that differ between the configuration of an open instance and the
+/// requested one.
+fn config_differences(
+    open: &BTreeMap<String, String>,
+    requested: &BTreeMap<String, String>,
+) -> String {
+    let describe = |value: Option<&String>| match value {
+        Some(value) => format!("{value:?}"),
+        None => "not set".into(),
+    };
+    open.keys()
+        .chain(requested.keys().filter(|key| !open.contains_key(*key)))
+        .filter(|key| open.get(*key) != requested.get(*key))
+        .map(|key| {
+            format!(
+                "`{key}` is {} instead of {}",
+                describe(open.get(key)),
+                describe(requested.get(key))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn open_error(error: *mut c_char) -> Error {
+    let message = if error.is_null() {
+        "unknown error".into()
+    } else {
+        let message = unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { duckdb_free(error.cast()) };
+        message
+    };
+    Error::Configuration(
+        format!("Error while opening a (possibly cached) database instance: `{message}`").into(),
+    )
+}
+
+#[derive(Debug)]
+struct DatabaseInstance {
+    database: CBox<duckdb_database>,
+    /// Path of the instance in the cache, `None` when it is not cached.
+    instance_path: Option<String>,
+    /// Options the instance was opened with.
+    options: DuckDBConnectOptions,
+    connections: AtomicUsize,
+}
+
+/// Handle to an open DuckDB database instance.
+///
+/// Databases are cached by path, opening a database that is already open (also through
+/// [DuckDBConnection::connect](sqlx_core::connection::Connection::connect)) returns the same
+/// instance, according to [InstanceReuse]. The cache keeps the database open until it is
+/// [closed](Self::close) or [evicted](Self::evict), after that it is closed as soon as its last
+/// handle and connection are dropped. An unnamed `:memory:` database is not cached, each
+/// [open](Self::open) creates a new one.
+#[derive(Debug, Clone)]
+pub struct DuckDBDatabase {
+    instance: Arc<DatabaseInstance>,
+}
+
+impl DuckDBDatabase {
+    /// Opens the database described by the options, or returns the instance already open.
+    pub async fn open(options: &DuckDBConnectOptions) -> Result<Self> {
+        // An unnamed in-memory database belongs to the connection opening it
+        if options.instance_reuse == InstanceReuse::Never || is_unnamed_memory(&options.path) {
+            let options = options.clone();
+            return spawn_blocking(move || Self::open_uncached(&options)).await;
+        }
+        let instance_path = instance_path(&options.path);
+        if let Some(open) = open_databases().lock().unwrap().get(&instance_path) {
+            return Self::reuse(open, options);
+        }
+        // Opening a file can be slow, e.g. to replay its WAL, the other databases can be opened
+        // in the meantime
+        let opened = spawn_blocking({
+            let options = options.clone();
+            let instance_path = instance_path.clone();
+            move || Self::open_cached(&options, instance_path)
+        })
+        .await?;
+        let mut open_databases = open_databases().lock().unwrap();
+        // Opened concurrently by another task, the DuckDB cache returned the same instance
+        if let Some(open) = open_databases.get(&instance_path) {
+            return Self::reuse(open, options);
+        }
+        open_databases.insert(instance_path, opened.clone());
+        Ok(opened)
+    }
+
+    /// Returns the open database, when it can be used with the requested options.
+    fn reuse(open: &DuckDBDatabase, options: &DuckDBConnectOptions) -> Result<Self> {
+        let open_config = open.instance.options.instance_config();
+        let requested = options.instance_config();
+        if open_config != requested {
+            let differences = config_differences(&open_config, &requested);
+            if options.instance_reuse == InstanceReuse::Strict {
+                return Err(Error::Configuration(
+                    format!(
+                        "The database `{}` is already open with a different configuration: \
+                        {differences}. Use `instance_reuse=lenient` to connect to it anyway",
+                        options.path
+                    )
+                    .into(),
+                ));
+            }
+            log::warn!(
+                "Reusing the open database `{}` with a different configuration: {differences}",
+                options.path
+            );
+        }
+        Ok(open.clone())
+    }
+
+    /// Opens a database instance of its own, not shared through the cache.
+    fn open_uncached(options: &DuckDBConnectOptions) -> Result<Self> {
+        let config = options.create_duckdb_config()?;
+        // Only the cache understands named in-memory databases, otherwise it would be a file
+        let path = if options.path.starts_with(":memory:") {
+            c":memory:".to_owned()
+        } else {
+            make_cstring(&options.path)?
+        };
+        let mut database: duckdb_database = null_mut();
+        let mut error: *mut c_char = null_mut();
+        let rc = unsafe { duckdb_open_ext(path.as_ptr(), &mut database, *config, &mut error) };
+        if rc != duckdb_state_DuckDBSuccess {
+            return Err(open_error(error));
+        }
+        Ok(Self::new(database, None, options))
+    }
+
+    /// Opens the database through the DuckDB instance cache, so that a file is opened once.
+    fn open_cached(options: &DuckDBConnectOptions, instance_path: String) -> Result<Self> {
+        let config = options.create_duckdb_config()?;
+        let path = make_cstring(&options.path)?;
+        let mut database: duckdb_database = null_mut();
+        let mut error: *mut c_char = null_mut();
+        let rc = unsafe {
+            duckdb_get_or_create_from_cache(
+                duckdb_instance_cache().load(Ordering::Relaxed),
+                path.as_ptr(),
+                &mut database,
+                *config,
+                &mut error,
+            )
+        };
+        if rc != duckdb_state_DuckDBSuccess {
+            return Err(open_error(error));
+        }
+        Ok(Self::new(database, Some(instance_path), options))
+    }
+
+    fn new(
+        database: duckdb_database,
+        instance_path: Option<String>,
+        options: &DuckDBConnectOptions,
+    ) -> Self {
+        Self {
+            instance: Arc::new(DatabaseInstance {
+                database: CBox::new(database, |mut database| unsafe {
+                    duckdb_close(&mut database)
+                }),
+                instance_path,
+                options: options.clone(),
+                connections: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Opens a new connection, configured with the options the database was opened with.
+    pub async fn connect(&self) -> Result<DuckDBConnection> {
+        DuckDBConnection::open(self, &self.instance.options).await
+    }
+
+    /// Options the database was opened with.
+    pub(crate) fn options(&self) -> &DuckDBConnectOptions {
+        &self.instance.options
+    }
+
+    /// Number of connections to this database that are still open.
+    pub fn connection_count(&self) -> usize {
+        self.instance.connections.load(Ordering::Relaxed)
+    }
+
+    /// Resolves the table names that DuckDB cannot find with `callback`, on every connection to
+    /// this database.
+    ///
+    /// When `callback` returns a [DuckDBReplacementScan], the table is replaced by a call to its
+    /// table function, otherwise the next replacement scan is tried.
+    pub fn add_replacement_scan<F>(&self, callback: F)
+    where
+        F: Fn(&str) -> Result<Option<DuckDBReplacementScan>, BoxDynError> + Send + Sync + 'static,
+    {
+        replacement_scan::add_replacement_scan(*self.instance.database, Box::new(callback));
+    }
+
+    /// Removes the database from the cache and closes it.
+    ///
+    /// Fails if the database is still used by connections or other handles, in that case it is
+    /// closed once they are dropped.
+    pub async fn close(self) -> Result<()> {
+        if let Some(path) = &self.instance.instance_path {
+            let mut open_databases = open_databases().lock().unwrap();
+            if open_databases
+                .get(path)
+                .is_some_and(|open| Arc::ptr_eq(&open.instance, &self.instance))
+            {
+                open_databases.remove(path);
+            }
+        }
+        let instance = Arc::try_unwrap(self.instance).map_err(|instance| {
+            Error::Configuration(
+                format!(
+                    "The database `{}` is still used by {} connections or other handles",
+                    instance.options.path,
+                    instance.connections.load(Ordering::Relaxed)
+                )
+                .into(),
+            )
+        })?;
+        // Closing writes the pending changes to the database file
+        spawn_blocking(move || drop(instance)).await;
+        Ok(())
+    }
+
+    /// Removes the database at `path` from the cache, it is closed as soon as its last handle and
+    /// connection are dropped. Returns `false` if the database was not in the cache.
+    pub fn evict(path: &str) -> bool {
+        open_databases()
+            .lock()
+            .unwrap()
+            .remove(&instance_path(path))
+            .is_some()
+    }
+}
+
+/// DuckDB connection that keeps its database open until it is disconnected.
+#[derive(Debug)]
+pub(crate) struct ConnectionHandle {
+    connection: CBox<duckdb_connection>,
+    database: DuckDBDatabase,
+}
+
+impl ConnectionHandle {
+    pub(crate) fn new(database: &DuckDBDatabase) -> Result<Self> {
+        let mut connection: duckdb_connection = null_mut();
+        let rc = unsafe { duckdb_connect(*database.instance.database, &mut connection) };
+        if rc != duckdb_state_DuckDBSuccess {
+            return Err(Error::Configuration(
+                format!(
+                    "Error connecting to the database `{}`",
+                    database.instance.options.path
+                )
+                .into(),
+            ));
+        }
+        database
+            .instance
+            .connections
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            connection: CBox::new(connection, |mut connection| unsafe {
+                duckdb_disconnect(&mut connection)
+            }),
+            database: database.clone(),
+        })
+    }
+
+    pub(crate) fn database(&self) -> &DuckDBDatabase {
+        &self.database
+    }
+}
+
+impl Deref for ConnectionHandle {
+    type Target = duckdb_connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.connection
+    }
+}
+
+impl Drop for ConnectionHandle {
+    fn drop(&mut self) {
+        self.database
+            .instance
+            .connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
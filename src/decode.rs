@@ -1,76 +1,459 @@
-use std::error::Error;
+use std::{
+    any::type_name,
+    collections::HashMap,
+    error::Error,
+    hash::{BuildHasher, Hash},
+};
 
-use crate::type_info::{DuckDBField, DuckdbDBTypeInfo};
+use crate::type_info::{DuckDBType, DuckdbDBTypeInfo};
+use crate::value::DuckDBValueData;
 use crate::{database::DuckDB, value::DuckDBValueRef};
+use rust_decimal::{
+    Decimal,
+    prelude::{FromPrimitive, ToPrimitive},
+};
 use sqlx_core::decode::Decode;
+use sqlx_core::error::UnexpectedNullError;
 use sqlx_core::types::Type;
 use sqlx_core::types::time;
-use sqlx_core::value::ValueRef;
 
 macro_rules! impl_trait {
-    ($duckdb_variant:path, $rust_type:path, Type) => {
+    ($duckdb_variant:ident, $rust_type:path, Type) => {
         impl Type<DuckDB> for $rust_type {
             fn type_info() -> DuckdbDBTypeInfo {
-                DuckdbDBTypeInfo::new($duckdb_variant(None))
+                DuckdbDBTypeInfo::new(DuckDBType::$duckdb_variant)
             }
         }
     };
-    ($duckdb_variant:path, $rust_type:path, Decode) => {
+    ($duckdb_variant:ident, $rust_type:path, Decode) => {
         impl<'r> Decode<'r, DuckDB> for $rust_type {
             fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
-                if let $duckdb_variant(Some(ref value), ..) = value.type_info().as_ref().field {
-                    Ok(value.clone().into())
-                } else {
-                    let error =
-                        if matches!(value.type_info().as_ref().field, $duckdb_variant(None, ..)) {
-                            "Tried to extract a value from a null field".to_string()
-                        } else {
-                            format!(
-                                "Cannot decode {} into {:?}",
-                                stringify!($duckdb_variant),
-                                value.type_info().as_ref().field,
-                            )
-                        };
-                    Err(error.into())
+                match value.data {
+                    DuckDBValueData::$duckdb_variant(data) => Ok(data.clone().into()),
+                    _ => Err(decode_error(&value, stringify!($rust_type))),
                 }
             }
         }
     };
-    ($duckdb_variant:path, $rust_type:path) => {
+    ($duckdb_variant:ident, $rust_type:path, Number) => {
+        impl_trait!($duckdb_variant, $rust_type, Type);
+        impl_trait!($rust_type, Number);
+    };
+    ($rust_type:path, Number) => {
+        impl<'r> Decode<'r, DuckDB> for $rust_type {
+            fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+                decode_number(value, false)
+            }
+        }
+
+        impl Type<DuckDB> for Lossy<$rust_type> {
+            fn type_info() -> DuckdbDBTypeInfo {
+                <$rust_type as Type<DuckDB>>::type_info()
+            }
+
+            fn compatible(ty: &DuckdbDBTypeInfo) -> bool {
+                ty.data_type.is_numeric()
+            }
+        }
+
+        impl<'r> Decode<'r, DuckDB> for Lossy<$rust_type> {
+            fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+                decode_number(value, true).map(Lossy)
+            }
+        }
+    };
+    ($duckdb_variant:ident, $rust_type:path) => {
         impl_trait!($duckdb_variant, $rust_type, Type);
         impl_trait!($duckdb_variant, $rust_type, Decode);
     };
 }
 
-impl_trait!(DuckDBField::Boolean, bool);
-impl_trait!(DuckDBField::Int8, i8);
-impl_trait!(DuckDBField::Int16, i16);
-impl_trait!(DuckDBField::Int32, i32);
-impl_trait!(DuckDBField::Int64, i64);
-impl_trait!(DuckDBField::Int128, i128);
-impl_trait!(DuckDBField::UInt8, u8);
-impl_trait!(DuckDBField::UInt16, u16);
-impl_trait!(DuckDBField::UInt32, u32);
-impl_trait!(DuckDBField::UInt64, u64);
-impl_trait!(DuckDBField::UInt128, u128);
-impl_trait!(DuckDBField::Float32, f32);
-impl_trait!(DuckDBField::Float64, f64);
-impl_trait!(DuckDBField::Decimal, ::rust_decimal::Decimal, Decode);
-impl_trait!(DuckDBField::Varchar, String);
-impl_trait!(DuckDBField::Blob, Box<[u8]>);
-impl_trait!(DuckDBField::Blob, Vec<u8>);
+impl_trait!(Boolean, bool);
+impl_trait!(Int8, i8, Number);
+impl_trait!(Int16, i16, Number);
+impl_trait!(Int32, i32, Number);
+impl_trait!(Int64, i64, Number);
+impl_trait!(Int128, i128, Number);
+impl_trait!(UInt8, u8, Number);
+impl_trait!(UInt16, u16, Number);
+impl_trait!(UInt32, u32, Number);
+impl_trait!(UInt64, u64, Number);
+impl_trait!(UInt128, u128, Number);
+impl_trait!(Float32, f32, Number);
+impl_trait!(Float64, f64, Number);
+impl_trait!(::rust_decimal::Decimal, Number);
+impl_trait!(Varchar, String, Type);
+impl_trait!(Blob, Box<[u8]>);
+impl_trait!(Blob, Vec<u8>);
 #[cfg(feature = "time")]
-impl_trait!(DuckDBField::Date, time::Date);
+impl_trait!(Date, time::Date);
 #[cfg(feature = "time")]
-impl_trait!(DuckDBField::Time, time::Time);
+impl_trait!(Time, time::Time);
 #[cfg(feature = "time")]
-impl_trait!(DuckDBField::Timestamp, time::PrimitiveDateTime);
+impl_trait!(Timestamp, time::PrimitiveDateTime);
 #[cfg(feature = "time")]
-impl_trait!(DuckDBField::TimestampWithTimezone, time::OffsetDateTime);
-impl_trait!(DuckDBField::Interval, crate::interval::Interval);
+impl_trait!(TimestampWithTimezone, time::OffsetDateTime);
+impl_trait!(Interval, crate::interval::Interval);
 
 impl Type<DuckDB> for rust_decimal::Decimal {
     fn type_info() -> DuckdbDBTypeInfo {
-        DuckdbDBTypeInfo::new(DuckDBField::Decimal(None, 0, 0))
+        DuckdbDBTypeInfo::new(DuckDBType::Decimal(0, 0))
+    }
+}
+
+/// Decodes any DuckDB number into `T`, even when it loses precision: decimals and floats lose
+/// their fractional part when decoded into an integer, and they are rounded to the closest value
+/// of a float or decimal. Values out of the range of `T` are still an error.
+///
+/// Without it, a number can be decoded into a Rust type able to hold all the values of its
+/// DuckDB type, like an `INTEGER` into `i64` or a `FLOAT` into `f64`.
+///
+/// ```no_run
+/// # use sqlx::{Connection, Row};
+/// # use sqlx_duckdb::{connection::DuckDBConnection, decode::Lossy};
+/// # async fn example() -> sqlx::Result<()> {
+/// let mut connection = DuckDBConnection::connect("duckdb://:memory:").await?;
+/// // SUM of a BIGINT column is a HUGEINT
+/// let row = sqlx::query("SELECT SUM(range) AS total FROM range(10)")
+///     .fetch_one(&mut connection)
+///     .await?;
+/// let Lossy(total) = row.try_get::<Lossy<i64>, _>("total")?;
+/// assert_eq!(total, 45);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lossy<T>(pub T);
+
+/// Rust number decoded from any DuckDB number it can hold. The conversions return `None` when
+/// the value is out of range, or when it would lose precision and `lossy` is `false`.
+trait Number: Sized {
+    fn from_i128(value: i128, lossy: bool) -> Option<Self>;
+
+    fn from_u128(value: u128, lossy: bool) -> Option<Self>;
+
+    fn from_f64(value: f64, lossy: bool) -> Option<Self>;
+
+    fn from_decimal(value: Decimal, lossy: bool) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($rust_type:ty) => {
+        impl Number for $rust_type {
+            fn from_i128(value: i128, _lossy: bool) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn from_u128(value: u128, _lossy: bool) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn from_f64(value: f64, lossy: bool) -> Option<Self> {
+                // Casting to i128 saturates, the values out of its range are rejected first
+                if !(lossy || value.fract() == 0.0)
+                    || !(i128::MIN as f64..i128::MAX as f64).contains(&value)
+                {
+                    return None;
+                }
+                <Self as Number>::from_i128(value as i128, lossy)
+            }
+
+            fn from_decimal(value: Decimal, lossy: bool) -> Option<Self> {
+                if !(lossy || value.fract().is_zero()) {
+                    return None;
+                }
+                <Self as Number>::from_i128(value.trunc().to_i128()?, lossy)
+            }
+        }
+    };
+}
+
+impl_integer!(i8);
+impl_integer!(i16);
+impl_integer!(i32);
+impl_integer!(i64);
+impl_integer!(i128);
+impl_integer!(u8);
+impl_integer!(u16);
+impl_integer!(u32);
+impl_integer!(u64);
+impl_integer!(u128);
+
+impl Number for f32 {
+    fn from_i128(value: i128, lossy: bool) -> Option<Self> {
+        let result = value as f32;
+        (lossy || result as i128 == value).then_some(result)
+    }
+
+    fn from_u128(value: u128, lossy: bool) -> Option<Self> {
+        let result = value as f32;
+        (lossy || result as u128 == value).then_some(result)
+    }
+
+    fn from_f64(value: f64, lossy: bool) -> Option<Self> {
+        let result = value as f32;
+        let exact = result as f64 == value || value.is_nan();
+        // Too large for a f32, the result is infinite
+        let in_range = result.is_finite() || !value.is_finite();
+        ((lossy || exact) && in_range).then_some(result)
+    }
+
+    fn from_decimal(value: Decimal, lossy: bool) -> Option<Self> {
+        let result = value.to_f32()?;
+        (lossy || Decimal::from_f32_retain(result) == Some(value)).then_some(result)
+    }
+}
+
+impl Number for f64 {
+    fn from_i128(value: i128, lossy: bool) -> Option<Self> {
+        let result = value as f64;
+        (lossy || result as i128 == value).then_some(result)
+    }
+
+    fn from_u128(value: u128, lossy: bool) -> Option<Self> {
+        let result = value as f64;
+        (lossy || result as u128 == value).then_some(result)
+    }
+
+    fn from_f64(value: f64, _lossy: bool) -> Option<Self> {
+        Some(value)
+    }
+
+    fn from_decimal(value: Decimal, lossy: bool) -> Option<Self> {
+        let result = value.to_f64()?;
+        (lossy || Decimal::from_f64_retain(result) == Some(value)).then_some(result)
+    }
+}
+
+impl Number for Decimal {
+    fn from_i128(value: i128, _lossy: bool) -> Option<Self> {
+        Decimal::try_from_i128_with_scale(value, 0).ok()
+    }
+
+    fn from_u128(value: u128, lossy: bool) -> Option<Self> {
+        <Self as Number>::from_i128(value.try_into().ok()?, lossy)
+    }
+
+    fn from_f64(value: f64, lossy: bool) -> Option<Self> {
+        if lossy {
+            <Decimal as FromPrimitive>::from_f64(value)
+        } else {
+            Decimal::from_f64_retain(value)
+        }
+    }
+
+    fn from_decimal(value: Decimal, _lossy: bool) -> Option<Self> {
+        Some(value)
+    }
+}
+
+fn decode_number<T: Number>(
+    value: DuckDBValueRef<'_>,
+    lossy: bool,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    type V = DuckDBValueData;
+    let result = match *value.data {
+        V::Int8(v) => T::from_i128(v.into(), lossy),
+        V::Int16(v) => T::from_i128(v.into(), lossy),
+        V::Int32(v) => T::from_i128(v.into(), lossy),
+        V::Int64(v) => T::from_i128(v.into(), lossy),
+        V::Int128(v) => T::from_i128(v, lossy),
+        V::UInt8(v) => T::from_i128(v.into(), lossy),
+        V::UInt16(v) => T::from_i128(v.into(), lossy),
+        V::UInt32(v) => T::from_i128(v.into(), lossy),
+        V::UInt64(v) => T::from_i128(v.into(), lossy),
+        V::UInt128(v) => T::from_u128(v, lossy),
+        V::Float32(v) => T::from_f64(v.into(), lossy),
+        V::Float64(v) => T::from_f64(v, lossy),
+        V::Decimal(v) => T::from_decimal(v, lossy),
+        _ => return Err(decode_error(&value, type_name::<T>())),
+    };
+    result.ok_or_else(|| {
+        format!(
+            "The value {:?} of type {} cannot be converted into {} without losing information",
+            value.data,
+            value.type_info,
+            type_name::<T>(),
+        )
+        .into()
+    })
+}
+
+impl<'r> Decode<'r, DuckDB> for String {
+    fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match value.data {
+            DuckDBValueData::Varchar(data) | DuckDBValueData::Json(data) => Ok(data.clone()),
+            _ => Err(decode_error(&value, "String")),
+        }
+    }
+}
+
+fn decode_error(value: &DuckDBValueRef<'_>, rust_type: &str) -> Box<dyn Error + Send + Sync> {
+    if *value.data == DuckDBValueData::Null {
+        return Box::new(UnexpectedNullError);
+    }
+    format!(
+        "Cannot decode {:?} of type {} into {}",
+        value.data, value.type_info, rust_type,
+    )
+    .into()
+}
+
+/// Decodes the children of a nested value, like the elements of a list, sharing their type.
+fn decode_children<'a, T, I>(
+    children: I,
+    type_info: &DuckdbDBTypeInfo,
+) -> Result<Vec<T>, Box<dyn Error + Send + Sync>>
+where
+    T: for<'r> Decode<'r, DuckDB>,
+    I: IntoIterator<Item = &'a DuckDBValueData>,
+{
+    children
+        .into_iter()
+        .map(|data| decode_child(data, type_info))
+        .collect()
+}
+
+/// Rust types decoded from the elements of a DuckDB `LIST` or `ARRAY` into a `Vec`, a `NULL`
+/// element is decoded into an `Option`. `u8` is left out because `Vec<u8>` is decoded from a
+/// `BLOB`.
+pub trait DuckDBListElement {}
+
+macro_rules! impl_list_element {
+    ($($rust_type:ty),+) => {
+        $(impl DuckDBListElement for $rust_type {})+
+    };
+}
+
+impl_list_element!(bool, i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64);
+impl_list_element!(
+    Decimal,
+    String,
+    Box<[u8]>,
+    Vec<u8>,
+    crate::interval::Interval
+);
+#[cfg(feature = "time")]
+impl_list_element!(
+    time::Date,
+    time::Time,
+    time::PrimitiveDateTime,
+    time::OffsetDateTime
+);
+
+impl<T: DuckDBListElement> DuckDBListElement for Option<T> {}
+
+impl<T: DuckDBListElement> DuckDBListElement for Vec<T> {}
+
+impl<K, V, S> DuckDBListElement for HashMap<K, V, S> {}
+
+impl<T> Type<DuckDB> for Vec<T>
+where
+    T: Type<DuckDB> + DuckDBListElement,
+{
+    fn type_info() -> DuckdbDBTypeInfo {
+        DuckdbDBTypeInfo::new(DuckDBType::List(T::type_info().data_type.into()))
+    }
+}
+
+impl<'r, T> Decode<'r, DuckDB> for Vec<T>
+where
+    T: for<'a> Decode<'a, DuckDB> + DuckDBListElement,
+{
+    fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match (value.data, &value.type_info.data_type) {
+            (DuckDBValueData::List(items), DuckDBType::List(_))
+            | (DuckDBValueData::List(items), DuckDBType::Array(..)) => {
+                decode_children(items, &value.type_info.children[0])
+            }
+            (DuckDBValueData::Array(items), DuckDBType::Array(..)) => {
+                decode_children(items.iter(), &value.type_info.children[0])
+            }
+            _ => Err(decode_error(&value, type_name::<Self>())),
+        }
     }
 }
+
+impl<K, V, S> Type<DuckDB> for HashMap<K, V, S>
+where
+    K: Type<DuckDB>,
+    V: Type<DuckDB>,
+{
+    fn type_info() -> DuckdbDBTypeInfo {
+        DuckdbDBTypeInfo::new(DuckDBType::Map(
+            K::type_info().data_type.into(),
+            V::type_info().data_type.into(),
+        ))
+    }
+}
+
+impl<'r, K, V, S> Decode<'r, DuckDB> for HashMap<K, V, S>
+where
+    K: for<'a> Decode<'a, DuckDB> + Eq + Hash,
+    V: for<'a> Decode<'a, DuckDB>,
+    S: BuildHasher + Default,
+{
+    fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (DuckDBValueData::Map(entries), DuckDBType::Map(..)) =
+            (value.data, &value.type_info.data_type)
+        else {
+            return Err(decode_error(&value, type_name::<Self>()));
+        };
+        let (key, val) = (&value.type_info.children[0], &value.type_info.children[1]);
+        let keys = decode_children::<K, _>(entries.iter().map(|(k, _)| k), key)?;
+        let values = decode_children::<V, _>(entries.iter().map(|(_, v)| v), val)?;
+        Ok(keys.into_iter().zip(values).collect())
+    }
+}
+
+/// Decodes a child of a nested value, like a field of a struct.
+fn decode_child<T>(
+    data: &DuckDBValueData,
+    type_info: &DuckdbDBTypeInfo,
+) -> Result<T, Box<dyn Error + Send + Sync>>
+where
+    T: for<'r> Decode<'r, DuckDB>,
+{
+    T::decode(DuckDBValueRef { data, type_info })
+}
+
+// A STRUCT is decoded into a tuple, matching the fields by position
+macro_rules! impl_tuple {
+    ($len:literal, $($name:ident),+) => {
+        impl<$($name: Type<DuckDB>),+> Type<DuckDB> for ($($name,)+) {
+            fn type_info() -> DuckdbDBTypeInfo {
+                DuckdbDBTypeInfo::new(DuckDBType::Struct(vec![
+                    $((String::new(), $name::type_info().data_type)),+
+                ]))
+            }
+        }
+
+        impl<'r, $($name: for<'a> Decode<'a, DuckDB>),+> Decode<'r, DuckDB> for ($($name,)+) {
+            fn decode(value: DuckDBValueRef<'r>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+                match (value.data, &value.type_info.data_type) {
+                    (DuckDBValueData::Struct(data), DuckDBType::Struct(_))
+                        if data.len() == $len && value.type_info.children.len() == $len =>
+                    {
+                        let mut fields = data.iter().zip(&value.type_info.children);
+                        Ok(($({
+                            let (data, type_info) = fields.next().unwrap();
+                            decode_child::<$name>(data, type_info)?
+                        },)+))
+                    }
+                    _ => Err(decode_error(&value, type_name::<Self>())),
+                }
+            }
+        }
+
+        impl<$($name),+> DuckDBListElement for ($($name,)+) {}
+    };
+}
+
+impl_tuple!(1, T1);
+impl_tuple!(2, T1, T2);
+impl_tuple!(3, T1, T2, T3);
+impl_tuple!(4, T1, T2, T3, T4);
+impl_tuple!(5, T1, T2, T3, T4, T5);
+impl_tuple!(6, T1, T2, T3, T4, T5, T6);
+impl_tuple!(7, T1, T2, T3, T4, T5, T6, T7);
+impl_tuple!(8, T1, T2, T3, T4, T5, T6, T7, T8);
@@ -1,13 +1,96 @@
+use libduckdb_sys::*;
+use sqlx_core::error::ErrorKind;
 use std::{
     error::Error,
+    ffi::CStr,
     fmt::{Display, Formatter},
     sync::OnceLock,
 };
 
+macro_rules! error_types {
+    ($($variant:ident => $raw:ident, $name:literal;)*) => {
+        /// Class of an error reported by DuckDB, it mirrors `duckdb_error_type`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum DuckDBErrorType {
+            $($variant,)*
+        }
+
+        impl DuckDBErrorType {
+            fn from_raw(value: duckdb_error_type) -> Self {
+                $(if value == $raw {
+                    return Self::$variant;
+                })*
+                Self::Invalid
+            }
+
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Name that DuckDB uses as prefix of the error messages, e.g. `Parser` in
+            /// `Parser Error: syntax error at or near "SELEC"`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+error_types! {
+    Invalid => duckdb_error_type_DUCKDB_ERROR_INVALID, "Invalid";
+    OutOfRange => duckdb_error_type_DUCKDB_ERROR_OUT_OF_RANGE, "Out of Range";
+    Conversion => duckdb_error_type_DUCKDB_ERROR_CONVERSION, "Conversion";
+    UnknownType => duckdb_error_type_DUCKDB_ERROR_UNKNOWN_TYPE, "Unknown Type";
+    Decimal => duckdb_error_type_DUCKDB_ERROR_DECIMAL, "Decimal";
+    MismatchType => duckdb_error_type_DUCKDB_ERROR_MISMATCH_TYPE, "Mismatch Type";
+    DivideByZero => duckdb_error_type_DUCKDB_ERROR_DIVIDE_BY_ZERO, "Divide by Zero";
+    ObjectSize => duckdb_error_type_DUCKDB_ERROR_OBJECT_SIZE, "Object Size";
+    InvalidType => duckdb_error_type_DUCKDB_ERROR_INVALID_TYPE, "Invalid type";
+    Serialization => duckdb_error_type_DUCKDB_ERROR_SERIALIZATION, "Serialization";
+    Transaction => duckdb_error_type_DUCKDB_ERROR_TRANSACTION, "TransactionContext";
+    NotImplemented => duckdb_error_type_DUCKDB_ERROR_NOT_IMPLEMENTED, "Not implemented";
+    Expression => duckdb_error_type_DUCKDB_ERROR_EXPRESSION, "Expression";
+    Catalog => duckdb_error_type_DUCKDB_ERROR_CATALOG, "Catalog";
+    Parser => duckdb_error_type_DUCKDB_ERROR_PARSER, "Parser";
+    Planner => duckdb_error_type_DUCKDB_ERROR_PLANNER, "Planner";
+    Scheduler => duckdb_error_type_DUCKDB_ERROR_SCHEDULER, "Scheduler";
+    Executor => duckdb_error_type_DUCKDB_ERROR_EXECUTOR, "Executor";
+    Constraint => duckdb_error_type_DUCKDB_ERROR_CONSTRAINT, "Constraint";
+    Index => duckdb_error_type_DUCKDB_ERROR_INDEX, "Index";
+    Stat => duckdb_error_type_DUCKDB_ERROR_STAT, "Stat";
+    Connection => duckdb_error_type_DUCKDB_ERROR_CONNECTION, "Connection";
+    Syntax => duckdb_error_type_DUCKDB_ERROR_SYNTAX, "Syntax";
+    Settings => duckdb_error_type_DUCKDB_ERROR_SETTINGS, "Settings";
+    Binder => duckdb_error_type_DUCKDB_ERROR_BINDER, "Binder";
+    Network => duckdb_error_type_DUCKDB_ERROR_NETWORK, "Network";
+    Optimizer => duckdb_error_type_DUCKDB_ERROR_OPTIMIZER, "Optimizer";
+    NullPointer => duckdb_error_type_DUCKDB_ERROR_NULL_POINTER, "NullPointer";
+    IO => duckdb_error_type_DUCKDB_ERROR_IO, "IO";
+    Interrupt => duckdb_error_type_DUCKDB_ERROR_INTERRUPT, "INTERRUPT";
+    Fatal => duckdb_error_type_DUCKDB_ERROR_FATAL, "FATAL";
+    Internal => duckdb_error_type_DUCKDB_ERROR_INTERNAL, "INTERNAL";
+    InvalidInput => duckdb_error_type_DUCKDB_ERROR_INVALID_INPUT, "Invalid Input";
+    OutOfMemory => duckdb_error_type_DUCKDB_ERROR_OUT_OF_MEMORY, "Out of Memory";
+    Permission => duckdb_error_type_DUCKDB_ERROR_PERMISSION, "Permission";
+    ParameterNotResolved => duckdb_error_type_DUCKDB_ERROR_PARAMETER_NOT_RESOLVED, "Parameter Not Resolved";
+    ParameterNotAllowed => duckdb_error_type_DUCKDB_ERROR_PARAMETER_NOT_ALLOWED, "Parameter Not Allowed";
+    Dependency => duckdb_error_type_DUCKDB_ERROR_DEPENDENCY, "Dependency";
+    Http => duckdb_error_type_DUCKDB_ERROR_HTTP, "HTTP";
+    MissingExtension => duckdb_error_type_DUCKDB_ERROR_MISSING_EXTENSION, "Missing Extension";
+    Autoload => duckdb_error_type_DUCKDB_ERROR_AUTOLOAD, "Extension Autoloading";
+    Sequence => duckdb_error_type_DUCKDB_ERROR_SEQUENCE, "Sequence";
+}
+
 #[derive(Debug)]
 pub struct DuckDBError {
     source: Option<Box<dyn Error + Send + Sync>>,
     message: OnceLock<String>,
+    error_type: Option<DuckDBErrorType>,
 }
 
 impl DuckDBError {
@@ -17,6 +100,7 @@ impl DuckDBError {
         Self {
             source: None,
             message,
+            error_type: None,
         }
     }
 
@@ -26,9 +110,48 @@ impl DuckDBError {
         Self {
             source: Some(source),
             message: lock,
+            error_type: None,
         }
     }
 
+    /// Error produced by DuckDB, the type is taken from the message prefix (`Binder Error: ...`).
+    ///
+    /// Used when the C API does not report the type, like for errors preparing a statement.
+    pub fn from_duckdb_message(message: String) -> Self {
+        let error_type = message
+            .split_once(" Error: ")
+            .and_then(|(name, _)| DuckDBErrorType::from_name(name));
+        Self {
+            error_type,
+            ..Self::new(message)
+        }
+    }
+
+    /// Error reported by DuckDB for a failed result, `None` if the result has no error.
+    pub(crate) unsafe fn from_result(result: &mut duckdb_result) -> Option<Self> {
+        unsafe {
+            let message = duckdb_result_error(result);
+            if message.is_null() {
+                return None;
+            }
+            Some(Self {
+                error_type: Some(DuckDBErrorType::from_raw(duckdb_result_error_type(result))),
+                ..Self::new(CStr::from_ptr(message).to_string_lossy().into_owned())
+            })
+        }
+    }
+
+    /// Class of the error, `None` if the error did not originate from DuckDB.
+    pub fn error_type(&self) -> Option<DuckDBErrorType> {
+        self.error_type
+    }
+
+    /// The query was stopped using a
+    /// [DuckDBInterruptHandle](crate::interrupt::DuckDBInterruptHandle).
+    pub fn is_interrupted(&self) -> bool {
+        self.error_type == Some(DuckDBErrorType::Interrupt)
+    }
+
     pub fn message(&self) -> &str {
         let mut result = self.message.get();
         if result.is_none() && self.source.is_some() {
@@ -81,7 +204,22 @@ impl sqlx_core::error::DatabaseError for DuckDBError {
         }
     }
 
-    fn kind(&self) -> sqlx_core::error::ErrorKind {
-        sqlx_core::error::ErrorKind::Other
+    fn kind(&self) -> ErrorKind {
+        if self.error_type != Some(DuckDBErrorType::Constraint) {
+            return ErrorKind::Other;
+        }
+        // DuckDB does not have a more specific type, the message tells which constraint failed
+        let message = self.message();
+        if message.contains("primary key constraint") || message.contains("unique constraint") {
+            ErrorKind::UniqueViolation
+        } else if message.contains("foreign key") {
+            ErrorKind::ForeignKeyViolation
+        } else if message.contains("NOT NULL constraint") {
+            ErrorKind::NotNullViolation
+        } else if message.contains("CHECK constraint") {
+            ErrorKind::CheckViolation
+        } else {
+            ErrorKind::Other
+        }
     }
 }
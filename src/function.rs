@@ -0,0 +1,623 @@
+use crate::{
+    cbox::CBox,
+    database::DuckDB,
+    error::DuckDBError,
+    extract_value::{DuckDBVector, extract_value},
+    options::make_cstring,
+    type_info::DuckdbDBTypeInfo,
+    value::DuckDBValueRef,
+};
+use libduckdb_sys::*;
+use sqlx_core::{Error, Result, decode::Decode, error::BoxDynError, types::Type};
+use std::{
+    any::{Any, type_name},
+    ffi::{CString, c_void},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+/// Column of the data chunk received by a function implemented in Rust.
+///
+/// It holds one value for each row processed by the function call.
+#[derive(Debug)]
+pub struct DuckDBChunkColumn {
+    vector: DuckDBVector,
+    type_info: DuckdbDBTypeInfo,
+    len: usize,
+}
+
+impl DuckDBChunkColumn {
+    pub(crate) unsafe fn new(vector: duckdb_vector, len: usize) -> Result<Self> {
+        let vector = unsafe { DuckDBVector::new(vector) };
+        let type_info = DuckdbDBTypeInfo::from_logical_type(vector.logical_type())?;
+        Ok(Self {
+            vector,
+            type_info,
+            len,
+        })
+    }
+
+    /// Columns of a data chunk.
+    pub(crate) unsafe fn from_chunk(chunk: duckdb_data_chunk) -> Result<Vec<Self>> {
+        unsafe {
+            let len = duckdb_data_chunk_get_size(chunk) as usize;
+            (0..duckdb_data_chunk_get_column_count(chunk))
+                .map(|i| Self::new(duckdb_data_chunk_get_vector(chunk, i), len))
+                .collect()
+        }
+    }
+
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Type of the values in the column.
+    pub fn type_info(&self) -> &DuckdbDBTypeInfo {
+        &self.type_info
+    }
+
+    /// Returns `true` if the value at `row` is `NULL`.
+    pub fn is_null(&self, row: usize) -> bool {
+        self.vector.is_null(row)
+    }
+
+    /// Decodes the value at `row`, `NULL` values can be decoded into an `Option`.
+    pub fn get<T>(&self, row: usize) -> Result<T>
+    where
+        T: for<'r> Decode<'r, DuckDB>,
+    {
+        if row >= self.len {
+            return Err(Error::Decode(
+                format!(
+                    "Row {row} is out of bounds, the column has {} rows",
+                    self.len
+                )
+                .into(),
+            ));
+        }
+        let data = extract_value(&self.vector, row)?;
+        T::decode(DuckDBValueRef {
+            data: &data,
+            type_info: &self.type_info,
+        })
+        .map_err(Error::Decode)
+    }
+
+    /// Decodes all the values of the column.
+    pub fn values<T>(&self) -> impl Iterator<Item = Result<T>> + '_
+    where
+        T: for<'r> Decode<'r, DuckDB>,
+    {
+        (0..self.len).map(|row| self.get(row))
+    }
+}
+
+/// Vector receiving the results of a function implemented in Rust.
+#[derive(Debug)]
+pub struct DuckDBOutputVector {
+    vector: duckdb_vector,
+    type_id: DUCKDB_TYPE,
+    capacity: usize,
+}
+
+impl DuckDBOutputVector {
+    pub(crate) unsafe fn new(vector: duckdb_vector) -> Self {
+        unsafe {
+            let mut logical_type = duckdb_vector_get_column_type(vector);
+            let type_id = duckdb_get_type_id(logical_type);
+            duckdb_destroy_logical_type(&mut logical_type);
+            Self {
+                vector,
+                type_id,
+                capacity: duckdb_vector_size() as usize,
+            }
+        }
+    }
+
+    /// Number of rows the vector can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Writes `value` at `row`.
+    pub fn set<T: IntoDuckDBValue>(&mut self, row: usize, value: T) -> Result<()> {
+        value.write(self, row)
+    }
+
+    /// Sets the value at `row` to `NULL`.
+    pub fn set_null(&mut self, row: usize) -> Result<()> {
+        self.check_row(row)?;
+        unsafe {
+            duckdb_vector_ensure_validity_writable(self.vector);
+            duckdb_validity_set_row_invalid(duckdb_vector_get_validity(self.vector), row as u64);
+        }
+        Ok(())
+    }
+
+    fn check_row(&self, row: usize) -> Result<()> {
+        if row < self.capacity {
+            Ok(())
+        } else {
+            Err(Error::Encode(
+                format!(
+                    "Cannot write row {row} into a vector of {} rows",
+                    self.capacity
+                )
+                .into(),
+            ))
+        }
+    }
+
+    fn check_type(&self, expected: DUCKDB_TYPE, rust_type: &str) -> Result<()> {
+        if self.type_id == expected {
+            Ok(())
+        } else {
+            Err(Error::Encode(
+                format!(
+                    "Cannot write {rust_type} into a vector of DuckDB type id {}",
+                    self.type_id
+                )
+                .into(),
+            ))
+        }
+    }
+
+    /// Writes a value having the same layout as the DuckDB type.
+    fn write_data<T: Copy>(&mut self, row: usize, type_id: DUCKDB_TYPE, value: T) -> Result<()> {
+        self.check_type(type_id, type_name::<T>())?;
+        self.check_row(row)?;
+        unsafe { *(duckdb_vector_get_data(self.vector) as *mut T).add(row) = value };
+        Ok(())
+    }
+
+    fn write_bytes<T>(&mut self, row: usize, type_id: DUCKDB_TYPE, value: &[u8]) -> Result<()> {
+        self.check_type(type_id, type_name::<T>())?;
+        self.check_row(row)?;
+        unsafe {
+            duckdb_vector_assign_string_element_len(
+                self.vector,
+                row as u64,
+                value.as_ptr().cast(),
+                value.len() as u64,
+            )
+        };
+        Ok(())
+    }
+}
+
+/// Rust value that can be returned by a function implemented in Rust.
+///
+/// The DuckDB type of the value is given by its [Type] implementation, custom types can be
+/// supported by writing them as one of the types already implementing this trait.
+pub trait IntoDuckDBValue: Type<DuckDB> {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()>;
+}
+
+macro_rules! impl_into_duckdb_value {
+    ($rust_type:ty, $type_id:path) => {
+        impl IntoDuckDBValue for $rust_type {
+            fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+                output.write_data(row, $type_id, self)
+            }
+        }
+    };
+}
+
+impl_into_duckdb_value!(bool, DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN);
+impl_into_duckdb_value!(i8, DUCKDB_TYPE_DUCKDB_TYPE_TINYINT);
+impl_into_duckdb_value!(i16, DUCKDB_TYPE_DUCKDB_TYPE_SMALLINT);
+impl_into_duckdb_value!(i32, DUCKDB_TYPE_DUCKDB_TYPE_INTEGER);
+impl_into_duckdb_value!(i64, DUCKDB_TYPE_DUCKDB_TYPE_BIGINT);
+impl_into_duckdb_value!(u8, DUCKDB_TYPE_DUCKDB_TYPE_UTINYINT);
+impl_into_duckdb_value!(u16, DUCKDB_TYPE_DUCKDB_TYPE_USMALLINT);
+impl_into_duckdb_value!(u32, DUCKDB_TYPE_DUCKDB_TYPE_UINTEGER);
+impl_into_duckdb_value!(u64, DUCKDB_TYPE_DUCKDB_TYPE_UBIGINT);
+impl_into_duckdb_value!(f32, DUCKDB_TYPE_DUCKDB_TYPE_FLOAT);
+impl_into_duckdb_value!(f64, DUCKDB_TYPE_DUCKDB_TYPE_DOUBLE);
+
+impl IntoDuckDBValue for i128 {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        let value = duckdb_hugeint {
+            lower: self as u64,
+            upper: (self >> 64) as i64,
+        };
+        output.write_data(row, DUCKDB_TYPE_DUCKDB_TYPE_HUGEINT, value)
+    }
+}
+
+impl IntoDuckDBValue for u128 {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        let value = duckdb_uhugeint {
+            lower: self as u64,
+            upper: (self >> 64) as u64,
+        };
+        output.write_data(row, DUCKDB_TYPE_DUCKDB_TYPE_UHUGEINT, value)
+    }
+}
+
+impl IntoDuckDBValue for String {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        output.write_bytes::<Self>(row, DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR, self.as_bytes())
+    }
+}
+
+impl IntoDuckDBValue for Vec<u8> {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        output.write_bytes::<Self>(row, DUCKDB_TYPE_DUCKDB_TYPE_BLOB, &self)
+    }
+}
+
+impl IntoDuckDBValue for Box<[u8]> {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        output.write_bytes::<Self>(row, DUCKDB_TYPE_DUCKDB_TYPE_BLOB, &self)
+    }
+}
+
+#[cfg(feature = "time")]
+impl IntoDuckDBValue for sqlx_core::types::time::Date {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        let value = unsafe {
+            duckdb_to_date(duckdb_date_struct {
+                year: self.year(),
+                month: self.month() as i8,
+                day: self.day() as i8,
+            })
+        };
+        output.write_data(row, DUCKDB_TYPE_DUCKDB_TYPE_DATE, value)
+    }
+}
+
+#[cfg(feature = "time")]
+impl IntoDuckDBValue for sqlx_core::types::time::Time {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        let value = unsafe {
+            duckdb_to_time(duckdb_time_struct {
+                hour: self.hour() as i8,
+                min: self.minute() as i8,
+                sec: self.second() as i8,
+                micros: self.microsecond() as i32,
+            })
+        };
+        output.write_data(row, DUCKDB_TYPE_DUCKDB_TYPE_TIME, value)
+    }
+}
+
+#[cfg(feature = "time")]
+impl IntoDuckDBValue for sqlx_core::types::time::PrimitiveDateTime {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        let micros = (self.assume_utc().unix_timestamp_nanos() / 1000) as i64;
+        output.write_data(
+            row,
+            DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP,
+            duckdb_timestamp { micros },
+        )
+    }
+}
+
+impl<T: IntoDuckDBValue> IntoDuckDBValue for Option<T> {
+    fn write(self, output: &mut DuckDBOutputVector, row: usize) -> Result<()> {
+        match self {
+            Some(value) => value.write(output, row),
+            None => output.set_null(row),
+        }
+    }
+}
+
+/// Message of an error or of a panic, reported to DuckDB by a function implemented in Rust.
+pub(crate) fn error_message(
+    error: std::result::Result<BoxDynError, Box<dyn Any + Send>>,
+) -> CString {
+    let message = match error {
+        Ok(error) => error.to_string(),
+        Err(panic) => match panic
+            .downcast_ref::<&str>()
+            .map(|v| v.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+        {
+            Some(message) => format!("The function panicked: {message}"),
+            None => "The function panicked".into(),
+        },
+    };
+    CString::new(message.replace('\0', "")).unwrap_or_default()
+}
+
+/// Calls `function`, catching its panics so that they do not unwind into DuckDB.
+pub(crate) fn call<T>(
+    function: impl FnOnce() -> std::result::Result<T, BoxDynError>,
+) -> std::result::Result<T, CString> {
+    match panic::catch_unwind(AssertUnwindSafe(function)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(error)) => Err(error_message(Ok(error))),
+        Err(panic) => Err(error_message(Err(panic))),
+    }
+}
+
+/// Destroys the Rust value stored as extra info of a DuckDB function.
+pub(crate) unsafe extern "C" fn destroy_boxed<T>(data: *mut c_void) {
+    drop(unsafe { Box::from_raw(data as *mut T) });
+}
+
+type ScalarFunction = Box<
+    dyn Fn(&[DuckDBChunkColumn], usize, &mut DuckDBOutputVector) -> Result<(), BoxDynError>
+        + Send
+        + Sync,
+>;
+
+unsafe extern "C" fn scalar_function_callback(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    output: duckdb_vector,
+) {
+    unsafe {
+        let function = &*(duckdb_scalar_function_get_extra_info(info) as *const ScalarFunction);
+        let rows = duckdb_data_chunk_get_size(input) as usize;
+        let mut output = DuckDBOutputVector::new(output);
+        let result = call(|| {
+            let columns = DuckDBChunkColumn::from_chunk(input)?;
+            function(&columns, rows, &mut output)
+        });
+        if let Err(message) = result {
+            duckdb_scalar_function_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+pub(crate) fn register_scalar_function<R, F>(
+    connection: duckdb_connection,
+    name: &str,
+    parameters: &[DuckdbDBTypeInfo],
+    function: F,
+) -> Result<()>
+where
+    R: IntoDuckDBValue,
+    F: Fn(&[DuckDBChunkColumn]) -> Result<Vec<R>, BoxDynError> + Send + Sync + 'static,
+{
+    let c_name = make_cstring(name)?;
+    let return_type = R::type_info().logical_type()?;
+    let parameters = parameters
+        .iter()
+        .map(DuckdbDBTypeInfo::logical_type)
+        .collect::<Result<Vec<_>>>()?;
+    let function: ScalarFunction = Box::new(move |columns, rows, output| {
+        let values = if columns.is_empty() {
+            // Without parameters the function can't know the number of rows, it is called for
+            // each of them
+            let mut values = Vec::with_capacity(rows);
+            for _ in 0..rows {
+                values.extend(function(columns)?);
+            }
+            values
+        } else {
+            function(columns)?
+        };
+        if values.len() != rows {
+            return Err(format!(
+                "The function returned {} values for {rows} rows",
+                values.len()
+            )
+            .into());
+        }
+        for (row, value) in values.into_iter().enumerate() {
+            output.set(row, value)?;
+        }
+        Ok(())
+    });
+    unsafe {
+        let scalar_function = CBox::new(duckdb_create_scalar_function(), |mut f| {
+            duckdb_destroy_scalar_function(&mut f)
+        });
+        duckdb_scalar_function_set_name(*scalar_function, c_name.as_ptr());
+        for parameter in &parameters {
+            duckdb_scalar_function_add_parameter(*scalar_function, **parameter);
+        }
+        duckdb_scalar_function_set_return_type(*scalar_function, *return_type);
+        duckdb_scalar_function_set_function(*scalar_function, Some(scalar_function_callback));
+        duckdb_scalar_function_set_extra_info(
+            *scalar_function,
+            Box::into_raw(Box::new(function)).cast(),
+            Some(destroy_boxed::<ScalarFunction>),
+        );
+        if duckdb_register_scalar_function(connection, *scalar_function)
+            != duckdb_state_DuckDBSuccess
+        {
+            return Err(DuckDBError::new(format!(
+                "Error while registering the scalar function `{name}`"
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Aggregate function implemented in Rust, registered with
+/// [DuckDBConnection::register_aggregate_function](crate::connection::DuckDBConnection::register_aggregate_function).
+///
+/// DuckDB creates a state for each group, updates it with the rows of the group and produces the
+/// result from it. Groups can be processed in parallel, then the partial states are combined.
+pub trait DuckDBAggregate: Send + Sync + 'static {
+    /// State accumulated for each group.
+    type State: Send + 'static;
+    /// Result of the aggregate for a group.
+    type Output: IntoDuckDBValue;
+
+    /// Creates the state of an empty group.
+    fn init(&self) -> Self::State;
+
+    /// Adds the values at `row` of the parameter columns to the state.
+    fn update(
+        &self,
+        state: &mut Self::State,
+        args: &[DuckDBChunkColumn],
+        row: usize,
+    ) -> Result<(), BoxDynError>;
+
+    /// Merges the partial state `source` into `target`.
+    fn combine(&self, target: &mut Self::State, source: &Self::State) -> Result<(), BoxDynError>;
+
+    /// Produces the result from the state.
+    fn finalize(&self, state: &Self::State) -> Result<Self::Output, BoxDynError>;
+}
+
+/// DuckDB allocates the aggregate states, each of them stores a pointer to the Rust state.
+unsafe fn aggregate_state<'a, S>(state: duckdb_aggregate_state) -> &'a mut *mut S {
+    unsafe { &mut *(state as *mut *mut S) }
+}
+
+/// The Rust state of an initialized aggregate state, a failed `init` leaves it empty.
+unsafe fn initialized_state<'a, S>(
+    state: duckdb_aggregate_state,
+) -> std::result::Result<&'a mut S, BoxDynError> {
+    let state = unsafe { *aggregate_state::<S>(state) };
+    if state.is_null() {
+        return Err("The aggregate state was not initialized".into());
+    }
+    Ok(unsafe { &mut *state })
+}
+
+unsafe extern "C" fn aggregate_state_size<S>(_info: duckdb_function_info) -> idx_t {
+    size_of::<*mut S>() as idx_t
+}
+
+unsafe extern "C" fn aggregate_init<A: DuckDBAggregate>(
+    info: duckdb_function_info,
+    state: duckdb_aggregate_state,
+) {
+    unsafe {
+        let aggregate = &*(duckdb_aggregate_function_get_extra_info(info) as *const A);
+        let state = aggregate_state::<A::State>(state);
+        *state = ptr::null_mut();
+        match call(|| Ok(aggregate.init())) {
+            Ok(value) => *state = Box::into_raw(Box::new(value)),
+            Err(message) => duckdb_aggregate_function_set_error(info, message.as_ptr()),
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_update<A: DuckDBAggregate>(
+    info: duckdb_function_info,
+    input: duckdb_data_chunk,
+    states: *mut duckdb_aggregate_state,
+) {
+    unsafe {
+        let aggregate = &*(duckdb_aggregate_function_get_extra_info(info) as *const A);
+        let rows = duckdb_data_chunk_get_size(input) as usize;
+        let result = call(|| {
+            let args = DuckDBChunkColumn::from_chunk(input)?;
+            for row in 0..rows {
+                let state = initialized_state::<A::State>(*states.add(row))?;
+                aggregate.update(state, &args, row)?;
+            }
+            Ok(())
+        });
+        if let Err(message) = result {
+            duckdb_aggregate_function_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_combine<A: DuckDBAggregate>(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    target: *mut duckdb_aggregate_state,
+    count: idx_t,
+) {
+    unsafe {
+        let aggregate = &*(duckdb_aggregate_function_get_extra_info(info) as *const A);
+        let result = call(|| {
+            for i in 0..count as usize {
+                let source = initialized_state::<A::State>(*source.add(i))?;
+                let target = initialized_state::<A::State>(*target.add(i))?;
+                aggregate.combine(target, source)?;
+            }
+            Ok(())
+        });
+        if let Err(message) = result {
+            duckdb_aggregate_function_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_finalize<A: DuckDBAggregate>(
+    info: duckdb_function_info,
+    source: *mut duckdb_aggregate_state,
+    result: duckdb_vector,
+    count: idx_t,
+    offset: idx_t,
+) {
+    unsafe {
+        let aggregate = &*(duckdb_aggregate_function_get_extra_info(info) as *const A);
+        let mut output = DuckDBOutputVector::new(result);
+        let result = call(|| {
+            for i in 0..count as usize {
+                let state = initialized_state::<A::State>(*source.add(i))?;
+                output.set(offset as usize + i, aggregate.finalize(state)?)?;
+            }
+            Ok(())
+        });
+        if let Err(message) = result {
+            duckdb_aggregate_function_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+unsafe extern "C" fn aggregate_destroy<S>(states: *mut duckdb_aggregate_state, count: idx_t) {
+    unsafe {
+        for i in 0..count as usize {
+            let state = aggregate_state::<S>(*states.add(i));
+            if !state.is_null() {
+                drop(Box::from_raw(*state));
+                *state = ptr::null_mut();
+            }
+        }
+    }
+}
+
+pub(crate) fn register_aggregate_function<A: DuckDBAggregate>(
+    connection: duckdb_connection,
+    name: &str,
+    parameters: &[DuckdbDBTypeInfo],
+    aggregate: A,
+) -> Result<()> {
+    let c_name = make_cstring(name)?;
+    let return_type = A::Output::type_info().logical_type()?;
+    let parameters = parameters
+        .iter()
+        .map(DuckdbDBTypeInfo::logical_type)
+        .collect::<Result<Vec<_>>>()?;
+    unsafe {
+        let function = CBox::new(duckdb_create_aggregate_function(), |mut f| {
+            duckdb_destroy_aggregate_function(&mut f)
+        });
+        duckdb_aggregate_function_set_name(*function, c_name.as_ptr());
+        for parameter in &parameters {
+            duckdb_aggregate_function_add_parameter(*function, **parameter);
+        }
+        duckdb_aggregate_function_set_return_type(*function, *return_type);
+        duckdb_aggregate_function_set_functions(
+            *function,
+            Some(aggregate_state_size::<A::State>),
+            Some(aggregate_init::<A>),
+            Some(aggregate_update::<A>),
+            Some(aggregate_combine::<A>),
+            Some(aggregate_finalize::<A>),
+        );
+        duckdb_aggregate_function_set_destructor(*function, Some(aggregate_destroy::<A::State>));
+        duckdb_aggregate_function_set_extra_info(
+            *function,
+            Box::into_raw(Box::new(aggregate)).cast(),
+            Some(destroy_boxed::<A>),
+        );
+        if duckdb_register_aggregate_function(connection, *function) != duckdb_state_DuckDBSuccess {
+            return Err(DuckDBError::new(format!(
+                "Error while registering the aggregate function `{name}`"
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::database::ConnectionHandle;
+use futures::{Stream, StreamExt, stream::BoxStream};
+use libduckdb_sys::duckdb_interrupt;
+use std::{
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{Context, Poll},
+};
+
+/// Handle used to interrupt the query running on a [DuckDBConnection](crate::connection::DuckDBConnection).
+///
+/// It can be cloned and sent to other tasks, it does not keep the connection alive.
+#[derive(Debug, Clone)]
+pub struct DuckDBInterruptHandle {
+    connection: Weak<ConnectionHandle>,
+}
+
+impl DuckDBInterruptHandle {
+    pub(crate) fn new(connection: &Arc<ConnectionHandle>) -> Self {
+        Self {
+            connection: Arc::downgrade(connection),
+        }
+    }
+
+    /// Interrupts the query currently executing on the connection.
+    ///
+    /// The interrupted query fails with an error for which
+    /// [DuckDBError::is_interrupted](crate::error::DuckDBError::is_interrupted) returns `true`.
+    /// Nothing happens if the connection is idle or was closed.
+    pub fn interrupt(&self) {
+        if let Some(connection) = self.connection.upgrade() {
+            unsafe { duckdb_interrupt(**connection) };
+        }
+    }
+}
+
+/// Stream of query results that interrupts the query when dropped before reaching the end.
+pub(crate) struct InterruptOnDrop<'a, T> {
+    stream: BoxStream<'a, T>,
+    handle: DuckDBInterruptHandle,
+    finished: bool,
+}
+
+impl<'a, T> InterruptOnDrop<'a, T> {
+    pub(crate) fn new(stream: BoxStream<'a, T>, handle: DuckDBInterruptHandle) -> Self {
+        Self {
+            stream,
+            handle,
+            finished: false,
+        }
+    }
+}
+
+impl<T> Stream for InterruptOnDrop<'_, T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let result = self.stream.poll_next_unpin(cx);
+        if let Poll::Ready(None) = result {
+            self.finished = true;
+        }
+        result
+    }
+}
+
+impl<T> Drop for InterruptOnDrop<'_, T> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.handle.interrupt();
+        }
+    }
+}
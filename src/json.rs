@@ -0,0 +1,68 @@
+use crate::{
+    arguments::DuckDBArgumentBuffer,
+    cbox::CBox,
+    database::DuckDB,
+    decode::DuckDBListElement,
+    type_info::{DuckDBType, DuckdbDBTypeInfo},
+    value::{DuckDBValueData, DuckDBValueRef},
+};
+use libduckdb_sys::*;
+use serde::{Serialize, de::DeserializeOwned};
+use sqlx_core::{
+    decode::Decode,
+    encode::{Encode, IsNull},
+    error::{BoxDynError, UnexpectedNullError},
+    types::{Json, JsonValue, Type},
+};
+use std::ffi::CStr;
+
+/// Returns `true` for the `JSON` type, a `VARCHAR` with the `JSON` alias.
+pub(crate) fn has_json_alias(logical_type: duckdb_logical_type) -> bool {
+    unsafe {
+        let alias = duckdb_logical_type_get_alias(logical_type);
+        if alias.is_null() {
+            return false;
+        }
+        let alias = CBox::new(alias, |v| duckdb_free(v.cast()));
+        CStr::from_ptr(*alias) == c"JSON"
+    }
+}
+
+// JsonValue is implemented by sqlx on top of these
+impl<T> Type<DuckDB> for Json<T> {
+    fn type_info() -> DuckdbDBTypeInfo {
+        DuckdbDBTypeInfo::new(DuckDBType::Json)
+    }
+
+    fn compatible(ty: &DuckdbDBTypeInfo) -> bool {
+        matches!(ty.data_type, DuckDBType::Json | DuckDBType::Varchar)
+    }
+}
+
+impl<'q, T: Serialize> Encode<'q, DuckDB> for Json<T> {
+    fn encode_by_ref(&self, buf: &mut DuckDBArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        buf.data = DuckDBValueData::Json(serde_json::to_string(&self.0)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl<'r, T: DeserializeOwned> Decode<'r, DuckDB> for Json<T> {
+    fn decode(value: DuckDBValueRef<'r>) -> Result<Self, BoxDynError> {
+        match value.data {
+            // Also a VARCHAR holding a JSON document
+            DuckDBValueData::Json(data) | DuckDBValueData::Varchar(data) => {
+                Ok(Json(serde_json::from_str(data)?))
+            }
+            DuckDBValueData::Null => Err(Box::new(UnexpectedNullError)),
+            data => Err(format!(
+                "Cannot decode {:?} of type {} into JSON",
+                data, value.type_info,
+            )
+            .into()),
+        }
+    }
+}
+
+impl<T> DuckDBListElement for Json<T> {}
+
+impl DuckDBListElement for JsonValue {}
@@ -1,3 +1,5 @@
+#[cfg(feature = "any")]
+pub mod any;
 pub mod arguments;
 pub mod cbox;
 pub mod column;
@@ -6,11 +8,21 @@ pub mod database;
 pub mod decode;
 pub mod error;
 pub mod extract_value;
+pub mod function;
+pub mod interrupt;
 pub mod interval;
+mod json;
+#[cfg(feature = "migrate")]
+pub mod migrate;
 pub mod options;
+pub mod progress;
 pub mod query_result;
+pub mod replacement_scan;
 pub mod row;
 pub mod statement;
+pub mod table_function;
+#[cfg(feature = "migrate")]
+mod testing;
 pub mod transaction;
 pub mod type_info;
 pub mod value;
@@ -0,0 +1,245 @@
+use crate::{
+    connection::DuckDBConnection,
+    database::{DuckDB, DuckDBDatabase},
+    options::DuckDBConnectOptions,
+};
+use futures::future::BoxFuture;
+use sqlx_core::{
+    Error,
+    connection::{ConnectOptions, Connection},
+    executor::Executor,
+    migrate::{AppliedMigration, Migrate, MigrateDatabase, MigrateError, Migration},
+    query_as::query_as,
+    rt::spawn_blocking,
+};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+pub(crate) fn is_in_memory(options: &DuckDBConnectOptions) -> bool {
+    options.path.is_empty() || options.path.starts_with(":memory:")
+}
+
+/// Files DuckDB creates next to the database file.
+pub(crate) fn database_files(path: &str) -> [PathBuf; 2] {
+    [PathBuf::from(path), PathBuf::from(format!("{path}.wal"))]
+}
+
+/// File locked while the migrations run, a database file can be opened by a single process but
+/// by many connections of that process.
+pub(crate) fn lock_file(path: &str) -> PathBuf {
+    PathBuf::from(format!("{path}.migrations.lock"))
+}
+
+pub(crate) fn remove_file(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl MigrateDatabase for DuckDB {
+    fn create_database(url: &str) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let options = DuckDBConnectOptions::from_str(url)?;
+            // Opening a connection creates the database file
+            options.connect().await?.close().await?;
+            if !is_in_memory(&options) {
+                // Otherwise the cache would keep the file open
+                DuckDBDatabase::evict(&options.path);
+            }
+            Ok(())
+        })
+    }
+
+    fn database_exists(url: &str) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            let options = DuckDBConnectOptions::from_str(url)?;
+            Ok(is_in_memory(&options) || Path::new(&options.path).exists())
+        })
+    }
+
+    fn drop_database(url: &str) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let options = DuckDBConnectOptions::from_str(url)?;
+            if is_in_memory(&options) {
+                return Ok(());
+            }
+            DuckDBDatabase::evict(&options.path);
+            for path in database_files(&options.path) {
+                remove_file(&path)?;
+            }
+            remove_file(&lock_file(&options.path))?;
+            Ok(())
+        })
+    }
+}
+
+impl Migrate for DuckDBConnection {
+    fn ensure_migrations_table(&mut self) -> BoxFuture<'_, Result<(), MigrateError>> {
+        Box::pin(async move {
+            self.execute(
+                r#"
+CREATE TABLE IF NOT EXISTS _sqlx_migrations (
+    version BIGINT PRIMARY KEY,
+    description TEXT NOT NULL,
+    installed_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    success BOOLEAN NOT NULL,
+    checksum BLOB NOT NULL,
+    execution_time BIGINT NOT NULL
+);
+                "#,
+            )
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn dirty_version(&mut self) -> BoxFuture<'_, Result<Option<i64>, MigrateError>> {
+        Box::pin(async move {
+            let row: Option<(i64,)> = query_as(
+                "SELECT version FROM _sqlx_migrations WHERE success = false ORDER BY version LIMIT 1",
+            )
+            .fetch_optional(self)
+            .await?;
+            Ok(row.map(|r| r.0))
+        })
+    }
+
+    fn list_applied_migrations(
+        &mut self,
+    ) -> BoxFuture<'_, Result<Vec<AppliedMigration>, MigrateError>> {
+        Box::pin(async move {
+            let rows: Vec<(i64, Vec<u8>)> =
+                query_as("SELECT version, checksum FROM _sqlx_migrations ORDER BY version")
+                    .fetch_all(self)
+                    .await?;
+            Ok(rows
+                .into_iter()
+                .map(|(version, checksum)| AppliedMigration {
+                    version,
+                    checksum: checksum.into(),
+                })
+                .collect())
+        })
+    }
+
+    fn lock(&mut self) -> BoxFuture<'_, Result<(), MigrateError>> {
+        Box::pin(async move {
+            let options = self.database().options();
+            if is_in_memory(options) || self.migration_lock.is_some() {
+                return Ok(());
+            }
+            let path = lock_file(&options.path);
+            // Waits for the other connections and processes running the migrations
+            let file = spawn_blocking(move || {
+                let file = File::options()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(path)?;
+                file.lock()?;
+                io::Result::Ok(file)
+            })
+            .await
+            .map_err(Error::from)?;
+            self.migration_lock = Some(file);
+            Ok(())
+        })
+    }
+
+    fn unlock(&mut self) -> BoxFuture<'_, Result<(), MigrateError>> {
+        Box::pin(async move {
+            if let Some(file) = self.migration_lock.take() {
+                file.unlock().map_err(Error::from)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn apply<'e: 'm, 'm>(
+        &'e mut self,
+        migration: &'m Migration,
+    ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            // The script and its bookkeeping are committed together, unless the migration opts
+            // out of the transaction. The execution time is only known after the commit.
+            let insert = format!(
+                r#"INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+                VALUES ({}, {}, TRUE, {}, -1)"#,
+                migration.version,
+                string_literal(&migration.description),
+                blob_literal(&migration.checksum),
+            );
+            if migration.no_tx {
+                execute_migration(self, migration).await?;
+                self.execute(&*insert).await?;
+            } else {
+                let mut tx = self.begin().await?;
+                execute_migration(&mut tx, migration).await?;
+                tx.execute(&*insert).await?;
+                tx.commit().await?;
+            }
+            let elapsed = start.elapsed();
+            self.execute(&*format!(
+                "UPDATE _sqlx_migrations SET execution_time = {} WHERE version = {}",
+                elapsed.as_nanos() as i64,
+                migration.version
+            ))
+            .await?;
+            Ok(elapsed)
+        })
+    }
+
+    fn revert<'e: 'm, 'm>(
+        &'e mut self,
+        migration: &'m Migration,
+    ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let delete = format!(
+                "DELETE FROM _sqlx_migrations WHERE version = {}",
+                migration.version
+            );
+            if migration.no_tx {
+                execute_migration(self, migration).await?;
+                self.execute(&*delete).await?;
+            } else {
+                let mut tx = self.begin().await?;
+                execute_migration(&mut tx, migration).await?;
+                tx.execute(&*delete).await?;
+                tx.commit().await?;
+            }
+            Ok(start.elapsed())
+        })
+    }
+}
+
+async fn execute_migration(
+    connection: &mut DuckDBConnection,
+    migration: &Migration,
+) -> Result<(), MigrateError> {
+    connection
+        .execute(&*migration.sql)
+        .await
+        .map_err(|e| MigrateError::ExecuteMigration(e, migration.version))?;
+    Ok(())
+}
+
+/// The queries are not parameterized, the values are written as SQL literals.
+fn string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn blob_literal(value: &[u8]) -> String {
+    let hex = value
+        .iter()
+        .map(|v| format!("\\x{v:02X}"))
+        .collect::<String>();
+    format!("'{hex}'::BLOB")
+}
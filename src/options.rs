@@ -1,20 +1,32 @@
 use crate::{cbox::CBox, connection::DuckDBConnection};
 use futures::future::BoxFuture;
 use libduckdb_sys::{
-    duckdb_config, duckdb_create_config, duckdb_destroy_config, duckdb_set_config,
-    duckdb_state_DuckDBSuccess,
+    duckdb_config, duckdb_config_count, duckdb_create_config, duckdb_destroy_config,
+    duckdb_get_config_flag, duckdb_set_config, duckdb_state_DuckDBSuccess,
 };
 use log::LevelFilter;
-use percent_encoding::percent_decode_str;
-use sqlx_core::{Error, Result, connection::ConnectOptions, url};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use sqlx_core::{Error, Result, Url, connection::ConnectOptions, url};
 use std::{
-    borrow::Cow,
+    collections::BTreeMap,
     ffi::{CStr, CString, c_char},
+    fmt::Display,
     ops::{Deref, DerefMut},
+    path::Path,
     ptr,
     str::FromStr,
+    sync::LazyLock,
 };
 
+/// Characters escaped in the database path of the url produced by `to_url_lossy`, everything but
+/// the unreserved characters and the path separator.
+const PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'/')
+    .remove(b'_')
+    .remove(b'~');
+
 #[derive(Debug, Clone, Copy)]
 pub enum AccessMode {
     ReadOnly,
@@ -38,6 +50,15 @@ impl TryFrom<&str> for AccessMode {
     }
 }
 
+impl AccessMode {
+    fn url_value(&self) -> &'static str {
+        match self {
+            AccessMode::ReadOnly => "ro",
+            AccessMode::ReadWrite => "rw",
+        }
+    }
+}
+
 impl From<&AccessMode> for *const c_char {
     fn from(value: &AccessMode) -> Self {
         match value {
@@ -48,20 +69,214 @@ impl From<&AccessMode> for *const c_char {
     }
 }
 
-#[derive(Default, Clone, Debug)]
+/// What to do when connecting to a database instance that is already open in this process.
+///
+/// DuckDB instances are cached by path, so that all the connections to the same database share
+/// the same instance and its configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InstanceReuse {
+    /// Reuse the cached instance, fail if it was created with different settings or mode.
+    #[default]
+    Strict,
+    /// Reuse the cached instance even if it was created with different settings or mode, the
+    /// requested configuration is ignored and a warning is logged.
+    Lenient,
+    /// Always create a new instance, owned by the connection and closed with it. Two instances
+    /// should not open the same database file, it is meant for in-memory databases.
+    Never,
+}
+
+impl TryFrom<&str> for InstanceReuse {
+    type Error = sqlx_core::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "strict" => Ok(InstanceReuse::Strict),
+            "lenient" => Ok(InstanceReuse::Lenient),
+            "never" => Ok(InstanceReuse::Never),
+            _ => Err(Error::Configuration(
+                format!(
+                    "Unknown value {value:?} for `instance_reuse`, expected one of: `strict`, \
+                    `lenient`, `never`"
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+impl InstanceReuse {
+    fn url_value(&self) -> &'static str {
+        match self {
+            InstanceReuse::Strict => "strict",
+            InstanceReuse::Lenient => "lenient",
+            InstanceReuse::Never => "never",
+        }
+    }
+}
+
+/// Order used by `ORDER BY` when the direction is not specified.
+#[derive(Debug, Clone, Copy)]
+pub enum DefaultOrder {
+    Ascending,
+    Descending,
+}
+
+impl DefaultOrder {
+    fn setting_value(&self) -> &'static str {
+        match self {
+            DefaultOrder::Ascending => "asc",
+            DefaultOrder::Descending => "desc",
+        }
+    }
+}
+
+/// DuckDB extension loaded on every new connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Extension {
+    /// Extension installed from the extension directory or, when missing, from the repository.
+    Named(String),
+    /// Extension loaded from a `.duckdb_extension` file.
+    Path(String),
+}
+
+impl Extension {
+    fn parse(value: &str) -> Self {
+        if value.ends_with(".duckdb_extension") {
+            Extension::Path(value.into())
+        } else {
+            Extension::Named(value.into())
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Extension::Named(name) => name,
+            Extension::Path(path) => path,
+        }
+    }
+}
+
+/// Number of rows buffered by default between the blocking DuckDB task and the stream returned
+/// by `fetch_many`, it matches the DuckDB vector size so that one data chunk fits in the buffer.
+pub const DEFAULT_ROW_BUFFER_SIZE: usize = 2048;
+
+/// Configuration option accepted by DuckDB, see [DuckDBConnectOptions::available_settings].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuckDBSetting {
+    pub name: String,
+    /// Description of the option, for the options provided by an extension it is the name of the
+    /// extension.
+    pub description: String,
+}
+
+static AVAILABLE_SETTINGS: LazyLock<Vec<DuckDBSetting>> = LazyLock::new(|| {
+    (0..unsafe { duckdb_config_count() })
+        .filter_map(|index| {
+            let mut name: *const c_char = ptr::null();
+            let mut description: *const c_char = ptr::null();
+            let rc = unsafe { duckdb_get_config_flag(index, &mut name, &mut description) };
+            if rc != duckdb_state_DuckDBSuccess || name.is_null() {
+                return None;
+            }
+            let to_string = |str: *const c_char| {
+                if str.is_null() {
+                    String::new()
+                } else {
+                    unsafe { CStr::from_ptr(str) }
+                        .to_string_lossy()
+                        .into_owned()
+                }
+            };
+            Some(DuckDBSetting {
+                name: to_string(name),
+                description: to_string(description),
+            })
+        })
+        .collect()
+});
+
+/// Number of single character edits needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != *cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Looks up a DuckDB configuration option by name, ignoring the case like DuckDB does.
+fn find_setting(key: &str) -> Result<&'static DuckDBSetting> {
+    let settings = DuckDBConnectOptions::available_settings();
+    if let Some(setting) = settings.iter().find(|s| s.name.eq_ignore_ascii_case(key)) {
+        return Ok(setting);
+    }
+    let key = key.to_lowercase();
+    let closest = settings
+        .iter()
+        .map(|s| (edit_distance(&key, &s.name), s))
+        .min_by_key(|(distance, _)| *distance)
+        .filter(|(distance, _)| *distance <= (key.len() / 3).max(2));
+    let mut message = format!("Unknown DuckDB setting `{key}`");
+    if let Some((_, setting)) = closest {
+        message += &format!(
+            ", did you mean `{}` ({})?",
+            setting.name, setting.description
+        );
+    }
+    Err(Error::Configuration(message.into()))
+}
+
+#[derive(Clone, Debug)]
 pub struct DuckDBConnectOptions {
-    pub(crate) path: CString,
+    pub(crate) path: String,
     pub(crate) access_mode: Option<AccessMode>,
-    pub(crate) settings: Vec<(CString, CString)>,
+    pub(crate) settings: Vec<(String, String)>,
+    pub(crate) row_buffer_size: usize,
+    pub(crate) track_progress: bool,
+    pub(crate) session_settings: Vec<(String, String)>,
+    pub(crate) after_connect: Vec<String>,
+    pub(crate) instance_reuse: InstanceReuse,
+    pub(crate) extensions: Vec<Extension>,
+}
+
+impl Default for DuckDBConnectOptions {
+    fn default() -> Self {
+        Self {
+            path: Default::default(),
+            access_mode: None,
+            settings: Vec::new(),
+            row_buffer_size: DEFAULT_ROW_BUFFER_SIZE,
+            track_progress: false,
+            session_settings: Vec::new(),
+            after_connect: Vec::new(),
+            instance_reuse: InstanceReuse::default(),
+            extensions: Vec::new(),
+        }
+    }
 }
 
-fn make_cstring(str: Cow<'_, str>) -> Result<CString> {
-    Ok(CString::new(&*str).map_err(|e| {
+pub(crate) fn make_cstring(str: &str) -> Result<CString> {
+    Ok(CString::new(str).map_err(|e| {
         Error::Configuration(format!("Error while creating the CString: {}", e).into())
     })?)
 }
 
+fn parse_param<T: FromStr<Err: Display>>(key: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|e| {
+        Error::Configuration(format!("Invalid value {value:?} for `{key}`: {e}").into())
+    })
+}
+
 impl DuckDBConnectOptions {
+    /// Parses the options from a `duckdb://` connection url.
     pub fn new(url: &str) -> Result<Self> {
         if !url.starts_with("duckdb://") {
             return Err(Error::Configuration(
@@ -78,18 +293,207 @@ impl DuckDBConnectOptions {
         let path = percent_decode_str(path).decode_utf8().map_err(|e| {
             Error::Configuration(format!("Error while decoding path string: {}", e).into())
         })?;
-        options.path = make_cstring(path)?;
+        options.path = path.into_owned();
         for (key, value) in url::form_urlencoded::parse(params.unwrap_or_default().as_bytes()) {
             match &*key {
                 "mode" => options.access_mode = Some(value.deref().try_into()?),
-                _ => options
-                    .settings
-                    .push((make_cstring(key)?, make_cstring(value)?)),
+                "row_buffer_size" => options.row_buffer_size = parse_param(&key, &value)?,
+                "track_progress" => options.track_progress = parse_param(&key, &value)?,
+                "instance_reuse" => options.instance_reuse = value.deref().try_into()?,
+                "extensions" => {
+                    for extension in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                        options = options.add_extension(Extension::parse(extension));
+                    }
+                }
+                _ => {
+                    find_setting(&key)?;
+                    options
+                        .settings
+                        .push((key.into_owned(), value.into_owned()))
+                }
             }
         }
         Ok(options)
     }
 
+    /// Configuration options accepted by DuckDB, either as connection url parameters or through
+    /// [setting](Self::setting), including the ones provided by the known extensions.
+    pub fn available_settings() -> &'static [DuckDBSetting] {
+        &AVAILABLE_SETTINGS
+    }
+
+    /// Options for a new in-memory database.
+    pub fn new_in_memory() -> Self {
+        Self::default().filename(":memory:")
+    }
+
+    /// Sets the path of the database file, `:memory:` opens an in-memory database.
+    pub fn filename(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = path.as_ref().to_string_lossy().into_owned();
+        self
+    }
+
+    /// Opens the database in read-only mode, it must already exist.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.access_mode = Some(if read_only {
+            AccessMode::ReadOnly
+        } else {
+            AccessMode::ReadWrite
+        });
+        self
+    }
+
+    /// Sets the number of threads used by DuckDB to execute queries.
+    pub fn threads(self, threads: usize) -> Self {
+        self.setting("threads", threads.to_string())
+    }
+
+    /// Sets the maximum amount of memory used by DuckDB.
+    pub fn memory_limit(self, bytes: u64) -> Self {
+        self.setting("memory_limit", format!("{bytes}B"))
+    }
+
+    /// Sets the directory where DuckDB spills data that does not fit in memory.
+    pub fn temp_directory(self, path: impl AsRef<Path>) -> Self {
+        self.setting("temp_directory", path.as_ref().to_string_lossy())
+    }
+
+    /// Sets the maximum size of the data spilled in the temporary directory.
+    pub fn max_temp_directory_size(self, bytes: u64) -> Self {
+        self.setting("max_temp_directory_size", format!("{bytes}B"))
+    }
+
+    /// Sets the order used by `ORDER BY` when the direction is not specified.
+    pub fn default_order(self, order: DefaultOrder) -> Self {
+        self.setting("default_order", order.setting_value())
+    }
+
+    /// Sets a DuckDB configuration option, replacing any previous value of the same option.
+    pub fn setting(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+        match self.settings.iter_mut().find(|(k, _)| *k == key) {
+            Some(setting) => setting.1 = value,
+            None => self.settings.push((key, value)),
+        }
+        self
+    }
+
+    /// Sets a DuckDB option with a `SET` statement on every new connection, replacing any previous
+    /// value of the same option.
+    ///
+    /// Unlike [setting](Self::setting), that is only used when the database instance is created,
+    /// it also applies to the connections opened on an instance that is already cached.
+    pub fn session_setting(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+        match self.session_settings.iter_mut().find(|(k, _)| *k == key) {
+            Some(setting) => setting.1 = value,
+            None => self.session_settings.push((key, value)),
+        }
+        self
+    }
+
+    /// Adds SQL executed on every new connection, after the session settings are applied.
+    ///
+    /// It can contain multiple statements, they are executed in the order they were added.
+    pub fn after_connect(mut self, sql: impl Into<String>) -> Self {
+        self.after_connect.push(sql.into());
+        self
+    }
+
+    /// Loads a DuckDB extension on every new connection, installing it first if it is not found in
+    /// the extension directory.
+    ///
+    /// It can also be set from the connection url using `extensions=json,icu`.
+    pub fn extension(self, name: impl Into<String>) -> Self {
+        self.add_extension(Extension::Named(name.into()))
+    }
+
+    /// Loads a DuckDB extension from a `.duckdb_extension` file on every new connection.
+    ///
+    /// Extensions that are not signed by DuckDB also require
+    /// [allow_unsigned_extensions](Self::allow_unsigned_extensions).
+    pub fn extension_from_path(self, path: impl AsRef<Path>) -> Self {
+        self.add_extension(Extension::Path(
+            path.as_ref().to_string_lossy().into_owned(),
+        ))
+    }
+
+    fn add_extension(mut self, extension: Extension) -> Self {
+        if !self.extensions.contains(&extension) {
+            self.extensions.push(extension);
+        }
+        self
+    }
+
+    /// Allows loading extensions that are not signed by DuckDB.
+    pub fn allow_unsigned_extensions(self, allow: bool) -> Self {
+        self.setting("allow_unsigned_extensions", allow.to_string())
+    }
+
+    /// Sets the directory where extensions are installed and looked up when loaded by name.
+    pub fn extension_directory(self, path: impl AsRef<Path>) -> Self {
+        self.setting("extension_directory", path.as_ref().to_string_lossy())
+    }
+
+    /// Sets the maximum number of rows that a streaming query (`fetch`, `fetch_many`) keeps in
+    /// memory ahead of the consumer.
+    ///
+    /// The query stops producing rows when the buffer is full and resumes once the consumer reads
+    /// from the stream. It can also be set from the connection url using `row_buffer_size=N`.
+    pub fn row_buffer_size(mut self, size: usize) -> Self {
+        self.row_buffer_size = size;
+        self
+    }
+
+    /// Enables the progress tracking of the queries, reported by
+    /// [DuckDBConnection::progress_handle](crate::connection::DuckDBConnection::progress_handle).
+    ///
+    /// It can also be set from the connection url using `track_progress=true`.
+    pub fn track_progress(mut self, track: bool) -> Self {
+        self.track_progress = track;
+        self
+    }
+
+    /// Sets what to do when the database instance is already open in this process.
+    ///
+    /// It can also be set from the connection url using `instance_reuse=strict|lenient|never`.
+    pub fn instance_reuse(mut self, reuse: InstanceReuse) -> Self {
+        self.instance_reuse = reuse;
+        self
+    }
+
+    /// Configuration of the database instance created with these options, the setting names are
+    /// lowercase because DuckDB ignores their case.
+    pub(crate) fn instance_config(&self) -> BTreeMap<String, String> {
+        self.settings
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), v.clone()))
+            .chain(
+                self.access_mode
+                    .map(|mode| ("mode".to_string(), mode.url_value().to_string())),
+            )
+            .collect()
+    }
+
+    /// SQL applying the session settings and the `after_connect` statements to a new connection.
+    pub(crate) fn connection_init_sql(&self) -> Result<Option<CString>> {
+        if self.session_settings.is_empty() && self.after_connect.is_empty() {
+            return Ok(None);
+        }
+        let mut sql = String::new();
+        for (key, value) in &self.session_settings {
+            let setting = find_setting(key)?;
+            sql += &format!("SET {} = '{}';\n", setting.name, value.replace('\'', "''"));
+        }
+        for statement in &self.after_connect {
+            sql += statement;
+            sql += ";\n";
+        }
+        make_cstring(&sql).map(Some)
+    }
+
     pub fn create_duckdb_config(&self) -> Result<CBox<duckdb_config>, sqlx_core::Error> {
         let mut config = CBox::new(ptr::null_mut(), |mut config| unsafe {
             duckdb_destroy_config(&mut config);
@@ -100,26 +504,31 @@ impl DuckDBConnectOptions {
                 "Error while creating the configuration object, most likely malloc failure".into(),
             ));
         }
-        for (key, value) in self
+        let settings = self
             .settings
             .iter()
-            .map(|(k, v)| (k.as_ptr(), v.as_ptr()))
+            .map(|(k, v)| Ok((find_setting(k)?, make_cstring(k)?, make_cstring(v)?)))
+            .collect::<Result<Vec<_>>>()?;
+        for (setting, key, value) in settings
+            .iter()
+            .map(|(s, k, v)| (Some(*s), k.as_ptr(), v.as_ptr()))
             .chain(
                 self.access_mode
                     .as_ref()
-                    .map(|mode| (c"access_mode".as_ptr(), mode.into()))
+                    .map(|mode| (None, c"access_mode".as_ptr(), mode.into()))
                     .into_iter(),
             )
         {
             let rc = unsafe { duckdb_set_config(*config, key.into(), value.into()) };
             if rc != duckdb_state_DuckDBSuccess {
-                return Err(Error::Configuration(
-                    format!(
-                        "Error while setting the option property `{}`",
-                        unsafe { CStr::from_ptr(key) }.to_str().unwrap()
-                    )
-                    .into(),
-                ));
+                let key = unsafe { CStr::from_ptr(key) }.to_string_lossy();
+                let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+                let mut message =
+                    format!("Error while setting the option property `{key}` to {value:?}");
+                if let Some(setting) = setting {
+                    message += &format!(" ({})", setting.description);
+                }
+                return Err(Error::Configuration(message.into()));
             }
         }
         Ok(config)
@@ -133,6 +542,44 @@ impl ConnectOptions for DuckDBConnectOptions {
         Self::new(url.as_str())
     }
 
+    fn to_url_lossy(&self) -> Url {
+        let mut params = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(mode) = &self.access_mode {
+            params.append_pair("mode", mode.url_value());
+        }
+        if self.row_buffer_size != DEFAULT_ROW_BUFFER_SIZE {
+            params.append_pair("row_buffer_size", &self.row_buffer_size.to_string());
+        }
+        if self.track_progress {
+            params.append_pair("track_progress", "true");
+        }
+        if self.instance_reuse != InstanceReuse::default() {
+            params.append_pair("instance_reuse", self.instance_reuse.url_value());
+        }
+        if !self.extensions.is_empty() {
+            let extensions = self
+                .extensions
+                .iter()
+                .map(Extension::name)
+                .collect::<Vec<_>>()
+                .join(",");
+            params.append_pair("extensions", &extensions);
+        }
+        for (key, value) in &self.settings {
+            params.append_pair(key, value);
+        }
+        let params = params.finish();
+        let mut url = format!(
+            "duckdb://{}",
+            utf8_percent_encode(&self.path, PATH_ENCODE_SET)
+        );
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params);
+        }
+        Url::parse(&url).expect("The encoded connection url must be valid")
+    }
+
     fn connect(&self) -> BoxFuture<'_, Result<Self::Connection, sqlx_core::Error>>
     where
         Self::Connection: Sized,
@@ -0,0 +1,49 @@
+use crate::database::ConnectionHandle;
+use libduckdb_sys::duckdb_query_progress;
+use std::sync::{Arc, Weak};
+
+/// Progress of the query running on a [DuckDBConnection](crate::connection::DuckDBConnection).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuckDBQueryProgress {
+    /// Percentage of the query completed, between 0 and 100.
+    pub percentage: f64,
+    pub rows_processed: u64,
+    /// Estimate of the total number of rows the query will process.
+    pub total_rows_to_process: u64,
+}
+
+/// Handle used to observe the progress of the query running on a
+/// [DuckDBConnection](crate::connection::DuckDBConnection).
+///
+/// It can be cloned and sent to other tasks, it does not keep the connection alive. Progress is
+/// only reported when the connection was opened with
+/// [DuckDBConnectOptions::track_progress](crate::options::DuckDBConnectOptions::track_progress).
+#[derive(Debug, Clone)]
+pub struct DuckDBProgressHandle {
+    connection: Weak<ConnectionHandle>,
+}
+
+impl DuckDBProgressHandle {
+    pub(crate) fn new(connection: &Arc<ConnectionHandle>) -> Self {
+        Self {
+            connection: Arc::downgrade(connection),
+        }
+    }
+
+    /// Returns the progress of the running query.
+    ///
+    /// Returns `None` if no query is running, its progress is not known yet or the connection was
+    /// closed.
+    pub fn progress(&self) -> Option<DuckDBQueryProgress> {
+        let connection = self.connection.upgrade()?;
+        let progress = unsafe { duckdb_query_progress(**connection) };
+        if progress.percentage < 0.0 {
+            return None;
+        }
+        Some(DuckDBQueryProgress {
+            percentage: progress.percentage,
+            rows_processed: progress.rows_processed,
+            total_rows_to_process: progress.total_rows_to_process,
+        })
+    }
+}
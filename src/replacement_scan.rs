@@ -0,0 +1,156 @@
+use crate::{
+    cbox::CBox,
+    database::DuckDB,
+    function::{call, destroy_boxed},
+    options::make_cstring,
+    type_info::{DuckDBType, DuckdbDBTypeInfo},
+    value::{DuckDBValue, DuckDBValueData},
+};
+use libduckdb_sys::*;
+use sqlx_core::{Result, error::BoxDynError, types::Type};
+use std::ffi::{CStr, c_char, c_void};
+
+/// Table function call replacing a table name that DuckDB could not find.
+#[derive(Debug, Clone)]
+pub struct DuckDBReplacementScan {
+    function: String,
+    parameters: Vec<DuckDBScanParameter>,
+}
+
+impl DuckDBReplacementScan {
+    /// Scans the table function `function`, e.g. `read_parquet`.
+    pub fn new(function: &str) -> Self {
+        Self {
+            function: function.into(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Adds a positional parameter to the table function call.
+    pub fn parameter(mut self, value: impl Into<DuckDBScanParameter>) -> Self {
+        self.parameters.push(value.into());
+        self
+    }
+}
+
+/// Parameter of a [DuckDBReplacementScan], created from a boolean, an integer, a float, a string
+/// or a vector of them.
+#[derive(Debug, Clone)]
+pub struct DuckDBScanParameter(DuckDBValue);
+
+macro_rules! impl_scan_parameter {
+    ($rust_type:ty, $variant:ident) => {
+        impl From<$rust_type> for DuckDBScanParameter {
+            fn from(value: $rust_type) -> Self {
+                Self(DuckDBValue {
+                    data: DuckDBValueData::$variant(value.into()),
+                    type_info: DuckDBType::$variant.into(),
+                })
+            }
+        }
+    };
+}
+
+impl_scan_parameter!(bool, Boolean);
+impl_scan_parameter!(i32, Int32);
+impl_scan_parameter!(i64, Int64);
+impl_scan_parameter!(f64, Float64);
+impl_scan_parameter!(String, Varchar);
+impl_scan_parameter!(&str, Varchar);
+
+impl<T> From<Vec<T>> for DuckDBScanParameter
+where
+    T: Into<DuckDBScanParameter> + Type<DuckDB>,
+{
+    fn from(value: Vec<T>) -> Self {
+        Self(DuckDBValue {
+            data: DuckDBValueData::List(value.into_iter().map(|v| v.into().0.data).collect()),
+            type_info: DuckDBType::List(T::type_info().data_type.into()).into(),
+        })
+    }
+}
+
+impl DuckDBScanParameter {
+    fn create_value(
+        data: &DuckDBValueData,
+        data_type: &DuckDBType,
+    ) -> Result<CBox<duckdb_value>, BoxDynError> {
+        type V = DuckDBValueData;
+        let value = unsafe {
+            match (data, data_type) {
+                (V::Boolean(v), _) => duckdb_create_bool(*v),
+                (V::Int32(v), _) => duckdb_create_int32(*v),
+                (V::Int64(v), _) => duckdb_create_int64(*v),
+                (V::Float64(v), _) => duckdb_create_double(*v),
+                (V::Varchar(v), _) => {
+                    duckdb_create_varchar_length(v.as_ptr().cast(), v.len() as idx_t)
+                }
+                (V::List(items), DuckDBType::List(child)) => {
+                    let logical_type =
+                        DuckdbDBTypeInfo::new(child.as_ref().clone()).logical_type()?;
+                    let items = items
+                        .iter()
+                        .map(|v| Self::create_value(v, child))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let mut values = items.iter().map(|v| **v).collect::<Vec<_>>();
+                    duckdb_create_list_value(
+                        *logical_type,
+                        values.as_mut_ptr(),
+                        values.len() as idx_t,
+                    )
+                }
+                _ => return Err(format!("Unsupported scan parameter {data:?}").into()),
+            }
+        };
+        if value.is_null() {
+            return Err(format!("Could not create the scan parameter {data:?}").into());
+        }
+        Ok(CBox::new(value, |mut v| unsafe {
+            duckdb_destroy_value(&mut v)
+        }))
+    }
+}
+
+pub(crate) type ReplacementScan =
+    Box<dyn Fn(&str) -> Result<Option<DuckDBReplacementScan>, BoxDynError> + Send + Sync>;
+
+unsafe extern "C" fn replacement_scan_callback(
+    info: duckdb_replacement_scan_info,
+    table_name: *const c_char,
+    data: *mut c_void,
+) {
+    unsafe {
+        let callback = &*(data as *const ReplacementScan);
+        let table_name = CStr::from_ptr(table_name).to_string_lossy();
+        let result = call(|| {
+            let Some(scan) = callback(&table_name)? else {
+                return Ok(());
+            };
+            let function = make_cstring(&scan.function)?;
+            let parameters = scan
+                .parameters
+                .iter()
+                .map(|v| DuckDBScanParameter::create_value(&v.0.data, &v.0.type_info.data_type))
+                .collect::<Result<Vec<_>, _>>()?;
+            duckdb_replacement_scan_set_function_name(info, function.as_ptr());
+            for parameter in &parameters {
+                duckdb_replacement_scan_add_parameter(info, **parameter);
+            }
+            Ok(())
+        });
+        if let Err(message) = result {
+            duckdb_replacement_scan_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+pub(crate) fn add_replacement_scan(database: duckdb_database, callback: ReplacementScan) {
+    unsafe {
+        duckdb_add_replacement_scan(
+            database,
+            Some(replacement_scan_callback),
+            Box::into_raw(Box::new(callback)).cast(),
+            Some(destroy_boxed::<ReplacementScan>),
+        )
+    };
+}
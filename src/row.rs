@@ -1,34 +1,45 @@
-use crate::value::DuckDBValueRef;
+use crate::value::{DuckDBValueData, DuckDBValueRef};
 use crate::{column::DuckDBColumn, database::DuckDB};
 use sqlx_core::Error;
 use sqlx_core::column::Column;
-use sqlx_core::{Result, column::ColumnIndex, row::Row};
+use sqlx_core::ext::ustr::UStr;
+use sqlx_core::{HashMap, Result, column::ColumnIndex, row::Row};
+use std::sync::Arc;
 
+/// A row of a query result, the column metadata is shared by all the rows of the same result.
 #[derive(Debug)]
-pub struct DuckDBRow(pub(crate) Vec<DuckDBColumn>);
+pub struct DuckDBRow {
+    pub(crate) values: Box<[DuckDBValueData]>,
+    pub(crate) columns: Arc<Vec<DuckDBColumn>>,
+    pub(crate) column_names: Arc<HashMap<UStr, usize>>,
+}
 
 impl Row for DuckDBRow {
     type Database = DuckDB;
 
     fn columns(&self) -> &[DuckDBColumn] {
-        &self.0
+        &self.columns
     }
 
     fn try_get_raw<I>(&self, index: I) -> Result<DuckDBValueRef<'_>>
     where
         I: ColumnIndex<Self>,
     {
-        Ok(self.0[index.index(self)?].value_ref())
+        let index = index.index(self)?;
+        Ok(DuckDBValueRef {
+            data: &self.values[index],
+            type_info: &self.columns[index].type_info,
+        })
     }
 }
 
 impl ColumnIndex<DuckDBRow> for &'_ str {
     fn index(&self, row: &DuckDBRow) -> Result<usize> {
-        row.0.iter().position(|v| v.name() == *self).ok_or_else(|| {
+        row.column_names.get(*self).copied().ok_or_else(|| {
             Error::ColumnNotFound(format!(
                 "Column {} not found, possible alternatives: {}",
                 self,
-                row.0
+                row.columns
                     .iter()
                     .map(|v| v.name())
                     .collect::<Vec<_>>()
@@ -40,13 +51,13 @@ impl ColumnIndex<DuckDBRow> for &'_ str {
 
 impl ColumnIndex<DuckDBRow> for usize {
     fn index(&self, row: &DuckDBRow) -> Result<usize> {
-        if *self < row.0.len() {
+        if *self < row.values.len() {
             Ok(*self)
         } else {
             Err(Error::ColumnNotFound(format!(
                 "Column {} not found, last column: {}",
                 self,
-                row.0.len()
+                row.values.len()
             )))
         }
     }
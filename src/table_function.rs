@@ -0,0 +1,420 @@
+use crate::{
+    cbox::CBox,
+    database::DuckDB,
+    error::DuckDBError,
+    extract_value::convert_date,
+    function::{DuckDBOutputVector, IntoDuckDBValue, call, destroy_boxed},
+    options::make_cstring,
+    type_info::{DuckDBType, DuckdbDBTypeInfo},
+    value::{DuckDBValue, DuckDBValueData},
+};
+use libduckdb_sys::*;
+use sqlx_core::{Error, Result, decode::Decode, error::BoxDynError, types::Type, value::Value};
+use std::{ffi::CStr, slice, sync::Mutex};
+
+/// Table function implemented in Rust, registered with
+/// [DuckDBConnection::register_table_function](crate::connection::DuckDBConnection::register_table_function).
+///
+/// A query calling the function first binds it, declaring the result columns from the
+/// parameters, then initializes a scan and produces the rows one chunk at a time.
+pub trait DuckDBTableFunction: Send + Sync + 'static {
+    /// Data produced by the bind phase, shared by the scans.
+    type BindData: Send + Sync + 'static;
+    /// State of a scan.
+    type InitData: Send + 'static;
+
+    /// Reads the parameters and declares the result columns.
+    fn bind(&self, info: &mut DuckDBBindInfo) -> Result<Self::BindData, BoxDynError>;
+
+    /// Creates the state of a new scan.
+    fn init(
+        &self,
+        bind_data: &Self::BindData,
+        info: &DuckDBInitInfo,
+    ) -> Result<Self::InitData, BoxDynError>;
+
+    /// Writes the next rows into `output`, the scan ends when no rows are written.
+    fn produce(
+        &self,
+        bind_data: &Self::BindData,
+        init_data: &mut Self::InitData,
+        output: &mut DuckDBOutputChunk,
+    ) -> Result<(), BoxDynError>;
+
+    /// Whether the function writes only the columns used by the query, listed by
+    /// [DuckDBInitInfo::projection].
+    fn projection_pushdown(&self) -> bool {
+        false
+    }
+}
+
+/// Parameters and result columns of a table function being bound.
+#[derive(Debug)]
+pub struct DuckDBBindInfo {
+    info: duckdb_bind_info,
+}
+
+impl DuckDBBindInfo {
+    /// Number of positional parameters.
+    pub fn parameter_count(&self) -> usize {
+        unsafe { duckdb_bind_get_parameter_count(self.info) as usize }
+    }
+
+    /// Decodes the positional parameter at `index`.
+    pub fn parameter<T>(&self, index: usize) -> Result<T>
+    where
+        T: for<'r> Decode<'r, DuckDB>,
+    {
+        if index >= self.parameter_count() {
+            return Err(Error::Decode(
+                format!(
+                    "Parameter {index} is out of bounds, the function has {} parameters",
+                    self.parameter_count()
+                )
+                .into(),
+            ));
+        }
+        let value = unsafe { duckdb_bind_get_parameter(self.info, index as idx_t) };
+        decode_parameter(value)
+    }
+
+    /// Decodes the named parameter `name`, returns `None` when the query does not set it.
+    pub fn named_parameter<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: for<'r> Decode<'r, DuckDB>,
+    {
+        let c_name = make_cstring(name)?;
+        let value = unsafe { duckdb_bind_get_named_parameter(self.info, c_name.as_ptr()) };
+        if value.is_null() {
+            return Ok(None);
+        }
+        decode_parameter(value).map(Some)
+    }
+
+    /// Adds a column to the result of the function.
+    pub fn add_result_column(&mut self, name: &str, type_info: &DuckdbDBTypeInfo) -> Result<()> {
+        let c_name = make_cstring(name)?;
+        let logical_type = type_info.logical_type()?;
+        unsafe { duckdb_bind_add_result_column(self.info, c_name.as_ptr(), *logical_type) };
+        Ok(())
+    }
+
+    /// Gives the optimizer an estimate of the number of rows produced.
+    pub fn set_cardinality(&mut self, rows: usize, is_exact: bool) {
+        unsafe { duckdb_bind_set_cardinality(self.info, rows as idx_t, is_exact) };
+    }
+}
+
+/// Scan of a table function being initialized.
+#[derive(Debug)]
+pub struct DuckDBInitInfo {
+    info: duckdb_init_info,
+}
+
+impl DuckDBInitInfo {
+    /// Indexes of the result columns used by the query, in the order of the output chunk.
+    ///
+    /// All the columns are produced unless [DuckDBTableFunction::projection_pushdown] is enabled.
+    pub fn projection(&self) -> Vec<usize> {
+        unsafe {
+            (0..duckdb_init_get_column_count(self.info))
+                .map(|i| duckdb_init_get_column_index(self.info, i) as usize)
+                .collect()
+        }
+    }
+}
+
+/// Data chunk receiving the rows produced by a table function.
+#[derive(Debug)]
+pub struct DuckDBOutputChunk {
+    chunk: duckdb_data_chunk,
+    columns: Vec<DuckDBOutputVector>,
+}
+
+impl DuckDBOutputChunk {
+    unsafe fn new(chunk: duckdb_data_chunk) -> Self {
+        unsafe {
+            let columns = (0..duckdb_data_chunk_get_column_count(chunk))
+                .map(|i| DuckDBOutputVector::new(duckdb_data_chunk_get_vector(chunk, i)))
+                .collect();
+            Self { chunk, columns }
+        }
+    }
+
+    /// Maximum number of rows of the chunk.
+    pub fn capacity(&self) -> usize {
+        unsafe { duckdb_vector_size() as usize }
+    }
+
+    /// Vectors of the chunk, one for each projected column.
+    pub fn columns(&mut self) -> &mut [DuckDBOutputVector] {
+        &mut self.columns
+    }
+
+    /// Sets the number of rows written, at most the [capacity](Self::capacity) of the chunk.
+    pub fn set_len(&mut self, rows: usize) -> Result<()> {
+        if rows > self.capacity() {
+            return Err(Error::Encode(
+                format!(
+                    "Cannot set the length of a chunk of {} rows to {rows}",
+                    self.capacity()
+                )
+                .into(),
+            ));
+        }
+        unsafe { duckdb_data_chunk_set_size(self.chunk, rows as idx_t) };
+        Ok(())
+    }
+}
+
+/// Rust value that can be returned as a row by a table function.
+///
+/// Implemented for tuples of values implementing [IntoDuckDBValue].
+pub trait IntoDuckDBRow {
+    /// Types of the columns.
+    fn types() -> Vec<DuckdbDBTypeInfo>;
+
+    /// Writes the values at `row` of the columns.
+    fn write(self, columns: &mut [DuckDBOutputVector], row: usize) -> Result<()>;
+}
+
+macro_rules! impl_into_duckdb_row {
+    ($($name:ident: $index:tt),+) => {
+        impl<$($name: IntoDuckDBValue),+> IntoDuckDBRow for ($($name,)+) {
+            fn types() -> Vec<DuckdbDBTypeInfo> {
+                vec![$(<$name as Type<DuckDB>>::type_info()),+]
+            }
+
+            fn write(self, columns: &mut [DuckDBOutputVector], row: usize) -> Result<()> {
+                $(columns[$index].set(row, self.$index)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_into_duckdb_row!(T0: 0);
+impl_into_duckdb_row!(T0: 0, T1: 1);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2, T3: 3);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6);
+impl_into_duckdb_row!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7);
+
+/// Table function producing the rows of the iterators created by a Rust closure, one for each
+/// scan.
+pub(crate) struct IteratorTableFunction<F> {
+    pub(crate) columns: Vec<String>,
+    pub(crate) iterator: F,
+}
+
+impl<F, I> DuckDBTableFunction for IteratorTableFunction<F>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+    I::Item: IntoDuckDBRow,
+{
+    type BindData = ();
+    type InitData = I::IntoIter;
+
+    fn bind(&self, info: &mut DuckDBBindInfo) -> Result<(), BoxDynError> {
+        let types = I::Item::types();
+        if types.len() != self.columns.len() {
+            return Err(format!(
+                "The rows have {} columns but {} names were given",
+                types.len(),
+                self.columns.len()
+            )
+            .into());
+        }
+        for (name, type_info) in self.columns.iter().zip(&types) {
+            info.add_result_column(name, type_info)?;
+        }
+        Ok(())
+    }
+
+    fn init(&self, _bind_data: &(), _info: &DuckDBInitInfo) -> Result<I::IntoIter, BoxDynError> {
+        Ok((self.iterator)().into_iter())
+    }
+
+    fn produce(
+        &self,
+        _bind_data: &(),
+        iterator: &mut I::IntoIter,
+        output: &mut DuckDBOutputChunk,
+    ) -> Result<(), BoxDynError> {
+        let mut rows = 0;
+        while rows < output.capacity() {
+            let Some(row) = iterator.next() else {
+                break;
+            };
+            row.write(output.columns(), rows)?;
+            rows += 1;
+        }
+        output.set_len(rows)?;
+        Ok(())
+    }
+}
+
+/// Converts a parameter value, types without a direct conversion are read as their string
+/// representation.
+fn parameter_value(value: duckdb_value) -> Result<DuckDBValue> {
+    type T = DuckDBType;
+    type V = DuckDBValueData;
+    unsafe {
+        let type_id = duckdb_get_type_id(duckdb_get_value_type(value));
+        if duckdb_is_null_value(value) {
+            return Ok(DuckDBValue {
+                data: V::Null,
+                type_info: T::Null.into(),
+            });
+        }
+        let (data_type, data) = match type_id {
+            DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN => (T::Boolean, V::Boolean(duckdb_get_bool(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_TINYINT => (T::Int8, V::Int8(duckdb_get_int8(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_SMALLINT => (T::Int16, V::Int16(duckdb_get_int16(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_INTEGER => (T::Int32, V::Int32(duckdb_get_int32(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_BIGINT => (T::Int64, V::Int64(duckdb_get_int64(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_UTINYINT => (T::UInt8, V::UInt8(duckdb_get_uint8(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_USMALLINT => (T::UInt16, V::UInt16(duckdb_get_uint16(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_UINTEGER => (T::UInt32, V::UInt32(duckdb_get_uint32(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_UBIGINT => (T::UInt64, V::UInt64(duckdb_get_uint64(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_FLOAT => (T::Float32, V::Float32(duckdb_get_float(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_DOUBLE => (T::Float64, V::Float64(duckdb_get_double(value))),
+            DUCKDB_TYPE_DUCKDB_TYPE_DATE => (
+                T::Date,
+                V::Date(convert_date(duckdb_from_date(duckdb_get_date(value)))?),
+            ),
+            DUCKDB_TYPE_DUCKDB_TYPE_BLOB => {
+                let blob = duckdb_get_blob(value);
+                let data = CBox::new(blob.data, |v| duckdb_free(v));
+                (
+                    T::Blob,
+                    V::Blob(slice::from_raw_parts(*data as *const u8, blob.size as usize).into()),
+                )
+            }
+            _ => {
+                let string = CBox::new(duckdb_get_varchar(value), |v| duckdb_free(v.cast()));
+                (
+                    T::Varchar,
+                    V::Varchar(CStr::from_ptr(*string).to_string_lossy().into_owned()),
+                )
+            }
+        };
+        Ok(DuckDBValue {
+            data,
+            type_info: data_type.into(),
+        })
+    }
+}
+
+fn decode_parameter<T>(value: duckdb_value) -> Result<T>
+where
+    T: for<'r> Decode<'r, DuckDB>,
+{
+    let value = CBox::new(value, |mut v| unsafe { duckdb_destroy_value(&mut v) });
+    T::decode(parameter_value(*value)?.as_ref()).map_err(Error::Decode)
+}
+
+unsafe extern "C" fn table_bind<T: DuckDBTableFunction>(info: duckdb_bind_info) {
+    unsafe {
+        let function = &*(duckdb_bind_get_extra_info(info) as *const T);
+        match call(|| function.bind(&mut DuckDBBindInfo { info })) {
+            Ok(bind_data) => duckdb_bind_set_bind_data(
+                info,
+                Box::into_raw(Box::new(bind_data)).cast(),
+                Some(destroy_boxed::<T::BindData>),
+            ),
+            Err(message) => duckdb_bind_set_error(info, message.as_ptr()),
+        }
+    }
+}
+
+unsafe extern "C" fn table_init<T: DuckDBTableFunction>(info: duckdb_init_info) {
+    unsafe {
+        let function = &*(duckdb_init_get_extra_info(info) as *const T);
+        let bind_data = &*(duckdb_init_get_bind_data(info) as *const T::BindData);
+        match call(|| function.init(bind_data, &DuckDBInitInfo { info })) {
+            // The chunks can be requested from different threads, the mutex serializes them
+            Ok(init_data) => duckdb_init_set_init_data(
+                info,
+                Box::into_raw(Box::new(Mutex::new(init_data))).cast(),
+                Some(destroy_boxed::<Mutex<T::InitData>>),
+            ),
+            Err(message) => duckdb_init_set_error(info, message.as_ptr()),
+        }
+    }
+}
+
+unsafe extern "C" fn table_produce<T: DuckDBTableFunction>(
+    info: duckdb_function_info,
+    output: duckdb_data_chunk,
+) {
+    unsafe {
+        let function = &*(duckdb_function_get_extra_info(info) as *const T);
+        let bind_data = &*(duckdb_function_get_bind_data(info) as *const T::BindData);
+        let init_data = &*(duckdb_function_get_init_data(info) as *const Mutex<T::InitData>);
+        let mut output = DuckDBOutputChunk::new(output);
+        let result = call(|| {
+            let mut init_data = init_data
+                .lock()
+                .map_err(|_| "The scan state is poisoned by a previous panic")?;
+            function.produce(bind_data, &mut init_data, &mut output)
+        });
+        if let Err(message) = result {
+            duckdb_function_set_error(info, message.as_ptr());
+        }
+    }
+}
+
+pub(crate) fn register_table_function<T: DuckDBTableFunction>(
+    connection: duckdb_connection,
+    name: &str,
+    parameters: &[DuckdbDBTypeInfo],
+    named_parameters: &[(&str, DuckdbDBTypeInfo)],
+    function: T,
+) -> Result<()> {
+    let c_name = make_cstring(name)?;
+    let parameters = parameters
+        .iter()
+        .map(DuckdbDBTypeInfo::logical_type)
+        .collect::<Result<Vec<_>>>()?;
+    let named_parameters = named_parameters
+        .iter()
+        .map(|(name, type_info)| Ok((make_cstring(name)?, type_info.logical_type()?)))
+        .collect::<Result<Vec<_>>>()?;
+    unsafe {
+        let table_function = CBox::new(duckdb_create_table_function(), |mut f| {
+            duckdb_destroy_table_function(&mut f)
+        });
+        duckdb_table_function_set_name(*table_function, c_name.as_ptr());
+        for parameter in &parameters {
+            duckdb_table_function_add_parameter(*table_function, **parameter);
+        }
+        for (name, parameter) in &named_parameters {
+            duckdb_table_function_add_named_parameter(*table_function, name.as_ptr(), **parameter);
+        }
+        duckdb_table_function_supports_projection_pushdown(
+            *table_function,
+            function.projection_pushdown(),
+        );
+        duckdb_table_function_set_bind(*table_function, Some(table_bind::<T>));
+        duckdb_table_function_set_init(*table_function, Some(table_init::<T>));
+        duckdb_table_function_set_function(*table_function, Some(table_produce::<T>));
+        duckdb_table_function_set_extra_info(
+            *table_function,
+            Box::into_raw(Box::new(function)).cast(),
+            Some(destroy_boxed::<T>),
+        );
+        if duckdb_register_table_function(connection, *table_function) != duckdb_state_DuckDBSuccess
+        {
+            return Err(DuckDBError::new(format!(
+                "Error while registering the table function `{name}`"
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::{
+    database::{DuckDB, DuckDBDatabase},
+    migrate::{database_files, is_in_memory, lock_file, remove_file},
+    options::{DuckDBConnectOptions, InstanceReuse},
+};
+use futures::future::BoxFuture;
+use sqlx_core::{
+    Error, Result,
+    pool::PoolOptions,
+    testing::{FixtureSnapshot, TestArgs, TestContext, TestSupport},
+};
+use std::{fs, io, path::PathBuf, str::FromStr};
+
+/// Directory of the test databases, when they are files.
+const BASE_PATH: &str = "target/sqlx/test-dbs";
+
+impl TestSupport for DuckDB {
+    fn test_context(args: &TestArgs) -> BoxFuture<'_, Result<TestContext<Self>>> {
+        Box::pin(async move { test_context(args) })
+    }
+
+    fn cleanup_test(db_name: &str) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            DuckDBDatabase::evict(db_name);
+            if !db_name.starts_with(":memory:") {
+                for path in database_files(db_name) {
+                    remove_file(&path)?;
+                }
+                remove_file(&lock_file(db_name))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn cleanup_test_dbs() -> BoxFuture<'static, Result<Option<usize>>> {
+        Box::pin(async move {
+            match fs::remove_dir_all(BASE_PATH) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                // The in-memory databases are gone with the process that created them
+                _ => Ok(None),
+            }
+        })
+    }
+
+    fn snapshot(_conn: &mut Self::Connection) -> BoxFuture<'_, Result<FixtureSnapshot<Self>>> {
+        Box::pin(async move {
+            Err(Error::Configuration(
+                "Fixture snapshots are not supported by DuckDB".into(),
+            ))
+        })
+    }
+}
+
+/// Each test gets its own database named after the test, in memory unless `DATABASE_URL` points
+/// to a DuckDB file, in which case the databases are files in [BASE_PATH]. The other parameters
+/// of `DATABASE_URL`, like the settings, are applied to the test databases.
+fn test_context(args: &TestArgs) -> Result<TestContext<DuckDB>> {
+    let options = match std::env::var("DATABASE_URL") {
+        Ok(url) if url.starts_with("duckdb://") => DuckDBConnectOptions::from_str(&url)?,
+        _ => DuckDBConnectOptions::new_in_memory(),
+    };
+    let db_name = if is_in_memory(&options) {
+        format!(":memory:{}", args.test_path)
+    } else {
+        let path = convert_path(args.test_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let path = path.to_string_lossy().into_owned();
+        // Left behind by a failed run of the same test
+        for file in database_files(&path) {
+            remove_file(&file)?;
+        }
+        path
+    };
+    // Left behind by a failed test in the same process
+    DuckDBDatabase::evict(&db_name);
+    Ok(TestContext {
+        // The connections of the pool and the one applying the migrations share the instance
+        connect_opts: options
+            .filename(&db_name)
+            .instance_reuse(InstanceReuse::Strict),
+        pool_opts: PoolOptions::new(),
+        db_name,
+    })
+}
+
+fn convert_path(test_path: &str) -> PathBuf {
+    let mut path = PathBuf::from(BASE_PATH);
+    for segment in test_path.split("::") {
+        path.push(segment);
+    }
+    path.set_extension("duckdb");
+    path
+}
@@ -1,6 +1,6 @@
 use crate::{connection::DuckDBConnection, database::DuckDB};
 use futures::future::BoxFuture;
-use sqlx_core::transaction::TransactionManager;
+use sqlx_core::{executor::Executor, transaction::TransactionManager};
 
 pub struct DuckDBTransactionManager;
 
@@ -8,18 +8,39 @@ impl TransactionManager for DuckDBTransactionManager {
     type Database = DuckDB;
 
     fn begin(conn: &mut DuckDBConnection) -> BoxFuture<'_, Result<(), sqlx_core::Error>> {
-        todo!()
+        Box::pin(async move {
+            // DuckDB rejects nested transactions, it does not support savepoints
+            conn.execute("BEGIN TRANSACTION").await?;
+            conn.transaction = true;
+            Ok(())
+        })
     }
 
     fn commit(conn: &mut DuckDBConnection) -> BoxFuture<'_, Result<(), sqlx_core::Error>> {
-        todo!()
+        Box::pin(async move {
+            if conn.transaction {
+                conn.execute("COMMIT").await?;
+                conn.transaction = false;
+            }
+            Ok(())
+        })
     }
 
     fn rollback(conn: &mut DuckDBConnection) -> BoxFuture<'_, Result<(), sqlx_core::Error>> {
-        todo!()
+        Box::pin(async move {
+            if conn.transaction {
+                conn.execute("ROLLBACK").await?;
+                conn.transaction = false;
+            }
+            Ok(())
+        })
     }
 
     fn start_rollback(conn: &mut DuckDBConnection) {
-        todo!()
+        if conn.transaction {
+            // Called while dropping the transaction, the query cannot be awaited
+            let _ = conn.execute_blocking(c"ROLLBACK");
+            conn.transaction = false;
+        }
     }
 }
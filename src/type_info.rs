@@ -1,118 +1,397 @@
-use crate::{error::DuckDBError, interval::Interval};
-use rust_decimal::Decimal;
-use sqlx_core::{
-    HashMap, Result,
-    ext::ustr::UStr,
-    type_info::TypeInfo,
-    types::{
-        JsonValue,
-        time::{Date, OffsetDateTime, PrimitiveDateTime, Time},
-    },
-};
-use std::fmt::Display;
-use uuid::Uuid;
+use crate::{cbox::CBox, error::DuckDBError, json::has_json_alias, options::make_cstring};
+use libduckdb_sys::*;
+use sqlx_core::{Result, ext::ustr::UStr, type_info::TypeInfo};
+use std::{ffi::CStr, fmt::Display};
 
-#[derive(Debug, Clone)]
-pub(crate) enum DuckDBField {
+/// DuckDB logical type, nested types hold the types of their children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum DuckDBType {
     Null,
-    Boolean(Option<bool>),
-    Int8(Option<i8>),
-    Int16(Option<i16>),
-    Int32(Option<i32>),
-    Int64(Option<i64>),
-    Int128(Option<i128>),
-    UInt8(Option<u8>),
-    UInt16(Option<u16>),
-    UInt32(Option<u32>),
-    UInt64(Option<u64>),
-    UInt128(Option<u128>),
-    Float32(Option<f32>),
-    Float64(Option<f64>),
-    Decimal(Option<Decimal>, /* prec: */ u8, /* scale: */ u8),
-    Varchar(Option<String>),
-    Blob(Option<Box<[u8]>>),
-    Date(Option<Date>),
-    Time(Option<Time>),
-    Timestamp(Option<PrimitiveDateTime>),
-    TimestampWithTimezone(Option<OffsetDateTime>),
-    Interval(Option<Interval>),
-    Uuid(Option<Uuid>),
-    Json(Option<JsonValue>),
-    Array(
-        Option<Box<[DuckDBField]>>,
-        /* type: */ Box<DuckDBField>,
-        /* len: */ u8,
-    ),
-    List(Option<Vec<DuckDBField>>, /* type: */ Box<DuckDBField>),
+    Boolean,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Int128,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    UInt128,
+    Float32,
+    Float64,
+    Decimal(/* width: */ u8, /* scale: */ u8),
+    Varchar,
+    Blob,
+    Date,
+    Time,
+    Timestamp,
+    TimestampWithTimezone,
+    Interval,
+    Uuid,
+    Json,
+    Array(Box<DuckDBType>, /* len: */ usize),
+    List(Box<DuckDBType>),
     Map(
-        Option<HashMap<DuckDBField, DuckDBField>>,
-        /* key: */ Box<DuckDBField>,
-        /* value: */ Box<DuckDBField>,
+        /* key: */ Box<DuckDBType>,
+        /* value: */ Box<DuckDBType>,
     ),
+    Struct(/* fields: */ Vec<(String, DuckDBType)>),
+}
+
+impl DuckDBType {
+    /// Smallest and largest value of an integer type.
+    fn integer_range(&self) -> Option<(i128, u128)> {
+        type T = DuckDBType;
+        let result = match self {
+            T::Int8 => (i8::MIN.into(), i8::MAX as u128),
+            T::Int16 => (i16::MIN.into(), i16::MAX as u128),
+            T::Int32 => (i32::MIN.into(), i32::MAX as u128),
+            T::Int64 => (i64::MIN.into(), i64::MAX as u128),
+            T::Int128 => (i128::MIN, i128::MAX as u128),
+            T::UInt8 => (0, u8::MAX.into()),
+            T::UInt16 => (0, u16::MAX.into()),
+            T::UInt32 => (0, u32::MAX.into()),
+            T::UInt64 => (0, u64::MAX.into()),
+            T::UInt128 => (0, u128::MAX),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Width and scale of the smallest decimal able to hold all the values of the type.
+    fn decimal_digits(&self) -> Option<(u8, u8)> {
+        type T = DuckDBType;
+        let result = match self {
+            T::Int8 | T::UInt8 => (3, 0),
+            T::Int16 | T::UInt16 => (5, 0),
+            T::Int32 | T::UInt32 => (10, 0),
+            T::Int64 => (19, 0),
+            T::UInt64 => (20, 0),
+            T::Int128 | T::UInt128 => (39, 0),
+            T::Decimal(width, scale) => (*width, *scale),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Number of bits of the mantissa of a floating point type.
+    fn mantissa_bits(&self) -> Option<u32> {
+        match self {
+            DuckDBType::Float32 => Some(f32::MANTISSA_DIGITS),
+            DuckDBType::Float64 => Some(f64::MANTISSA_DIGITS),
+            _ => None,
+        }
+    }
+
+    /// Element type of a LIST or ARRAY, key and value types of a MAP, field types of a STRUCT.
+    pub(crate) fn children(&self) -> Vec<&DuckDBType> {
+        match self {
+            DuckDBType::Array(child, _) | DuckDBType::List(child) => vec![child],
+            DuckDBType::Map(key, value) => vec![key, value],
+            DuckDBType::Struct(fields) => fields.iter().map(|(_, field)| field).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` for the integer, floating point and decimal types.
+    pub(crate) fn is_numeric(&self) -> bool {
+        self.decimal_digits().is_some() || self.mantissa_bits().is_some()
+    }
+
+    /// Returns `true` if the values of `source` can be decoded into the Rust type described by
+    /// this type.
+    pub(crate) fn accepts(&self, source: &DuckDBType) -> bool {
+        type T = DuckDBType;
+        match (self, source) {
+            _ if self == source => true,
+            // The children can be NULL, they are decoded into an Option
+            (_, T::Null) => true,
+            // JSON is stored as a string
+            (T::Varchar, T::Json) => true,
+            // The Rust decimal takes any width and scale
+            (T::Decimal(0, 0), T::Decimal(..)) => true,
+            (T::List(child), T::List(source) | T::Array(source, _)) => child.accepts(source),
+            (T::Map(key, value), T::Map(source_key, source_value)) => {
+                key.accepts(source_key) && value.accepts(source_value)
+            }
+            // The fields of a tuple have no name, they are matched by position
+            (T::Struct(fields), T::Struct(source)) => {
+                fields.len() == source.len()
+                    && fields
+                        .iter()
+                        .zip(source)
+                        .all(|((name, field), (source_name, source))| {
+                            (name.is_empty() || name == source_name) && field.accepts(source)
+                        })
+            }
+            _ => source.widens_to(self),
+        }
+    }
+
+    /// Returns `true` if every value of this type can be converted into `target` without losing
+    /// information: smaller integers into larger ones, unsigned integers into wider signed ones,
+    /// integers into floats and decimals with enough digits, `FLOAT` into `DOUBLE`.
+    pub(crate) fn widens_to(&self, target: &DuckDBType) -> bool {
+        if let Some((min, max)) = self.integer_range() {
+            if let Some((target_min, target_max)) = target.integer_range() {
+                return target_min <= min && max <= target_max;
+            }
+            if let Some(bits) = target.mantissa_bits() {
+                // Every integer up to 2^bits is exactly representable
+                return max.max(min.unsigned_abs()) <= 1 << bits;
+            }
+        }
+        match (self.decimal_digits(), target) {
+            // The Rust decimal holds 28 digits
+            (Some((width, 0)), DuckDBType::Decimal(0, 0)) => width <= 28,
+            (Some((width, scale)), DuckDBType::Decimal(target_width, target_scale)) => {
+                scale <= *target_scale && width - scale <= target_width - target_scale
+            }
+            _ => matches!((self, target), (DuckDBType::Float32, DuckDBType::Float64)),
+        }
+    }
 }
 
-/// Type information for a SQLite type.
+/// Type information for a DuckDB type, it describes the type only and never holds a value.
 #[derive(Debug, Clone)]
 pub struct DuckdbDBTypeInfo {
     pub(crate) type_name: UStr,
-    pub(crate) field: DuckDBField,
+    pub(crate) data_type: DuckDBType,
+    /// Types of the children of a nested type, in the order of [DuckDBType::children], shared by
+    /// all the nested values when they are decoded.
+    pub(crate) children: Box<[DuckdbDBTypeInfo]>,
 }
 
 impl DuckdbDBTypeInfo {
-    fn type_name(data_type: &DuckDBField) -> Result<UStr> {
-        let result = match data_type {
-            DuckDBField::Null => {
+    fn type_name(data_type: &DuckDBType) -> UStr {
+        type T = DuckDBType;
+        match data_type {
+            // Type of the NULL literal
+            T::Null => "NULL".into(),
+            T::Boolean => "BOOLEAN".into(),
+            T::Int8 => "TINYINT".into(),
+            T::Int16 => "SMALLINT".into(),
+            T::Int32 => "INTEGER".into(),
+            T::Int64 => "BIGINT".into(),
+            T::Int128 => "HUGEINT".into(),
+            T::UInt8 => "UTINYINT".into(),
+            T::UInt16 => "USMALLINT".into(),
+            T::UInt32 => "UINTEGER".into(),
+            T::UInt64 => "UBIGINT".into(),
+            T::UInt128 => "UHUGEINT".into(),
+            T::Float32 => "FLOAT".into(),
+            T::Float64 => "DOUBLE".into(),
+            T::Decimal(prec, scale) => format!("DECIMAL({}, {})", prec, scale).into(),
+            T::Varchar => "VARCHAR".into(),
+            T::Blob => "BLOB".into(),
+            T::Date => "DATE".into(),
+            T::Time => "TIME".into(),
+            T::Timestamp => "TIMESTAMP".into(),
+            T::TimestampWithTimezone => "TIMESTAMP WITH TIME ZONE".into(),
+            T::Interval => "INTERVAL".into(),
+            T::Uuid => "UUID".into(),
+            T::Json => "JSON".into(),
+            T::Array(t, l) => format!("{}[{}]", Self::type_name(t), l).into(),
+            T::List(data_type) => format!("{}[]", Self::type_name(data_type)).into(),
+            T::Map(k, v) => format!("MAP({}, {})", Self::type_name(k), Self::type_name(v)).into(),
+            T::Struct(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, data_type)| match name.as_str() {
+                        "" => Self::type_name(data_type).to_string(),
+                        _ => format!("{} {}", name, Self::type_name(data_type)),
+                    })
+                    .collect::<Vec<_>>();
+                format!("STRUCT({})", fields.join(", ")).into()
+            }
+        }
+    }
+
+    /// Creates the DuckDB logical type described by the type.
+    fn create_logical_type(data_type: &DuckDBType) -> Result<CBox<duckdb_logical_type>> {
+        type T = DuckDBType;
+        let type_id = match data_type {
+            T::Null => {
                 return Err(DuckDBError::new(
                     "The field is null and doesn't contain information about the type".into(),
                 )
                 .into());
             }
-            DuckDBField::Boolean(..) => "BOOLEAN".into(),
-            DuckDBField::Int8(..) => "TINYINT".into(),
-            DuckDBField::Int16(..) => "SMALLINT".into(),
-            DuckDBField::Int32(..) => "INTEGER".into(),
-            DuckDBField::Int64(..) => "BIGINT".into(),
-            DuckDBField::Int128(..) => "HUGEINT".into(),
-            DuckDBField::UInt8(..) => "UTINYINT".into(),
-            DuckDBField::UInt16(..) => "USMALLINT".into(),
-            DuckDBField::UInt32(..) => "UINTEGER".into(),
-            DuckDBField::UInt64(..) => "UBIGINT".into(),
-            DuckDBField::UInt128(..) => "UHUGEINT".into(),
-            DuckDBField::Float32(..) => "FLOAT".into(),
-            DuckDBField::Float64(..) => "DOUBLE".into(),
-            DuckDBField::Decimal(_, prec, scale) => format!("DECIMAL({}, {})", prec, scale).into(),
-            DuckDBField::Varchar(..) => "VARCHAR".into(),
-            DuckDBField::Blob(..) => "BLOB".into(),
-            DuckDBField::Date(..) => "DATE".into(),
-            DuckDBField::Time(..) => "TIME".into(),
-            DuckDBField::Timestamp(..) => "TIMESTAMP".into(),
-            DuckDBField::TimestampWithTimezone(..) => "TIMESTAMP WITH TIME ZONE".into(),
-            DuckDBField::Interval(..) => "INTERVAL".into(),
-            DuckDBField::Uuid(..) => "UUID".into(),
-            DuckDBField::Json(..) => "JSON".into(),
-            DuckDBField::Array(.., t, l) => format!("{}[{}]", Self::type_name(t)?, l).into(),
-            DuckDBField::List(.., data_type) => format!("{}[]", Self::type_name(data_type)?).into(),
-            DuckDBField::Map(.., k, v) => {
-                format!("MAP({}, {})", Self::type_name(k)?, Self::type_name(v)?).into()
+            T::Boolean => DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN,
+            T::Int8 => DUCKDB_TYPE_DUCKDB_TYPE_TINYINT,
+            T::Int16 => DUCKDB_TYPE_DUCKDB_TYPE_SMALLINT,
+            T::Int32 => DUCKDB_TYPE_DUCKDB_TYPE_INTEGER,
+            T::Int64 => DUCKDB_TYPE_DUCKDB_TYPE_BIGINT,
+            T::Int128 => DUCKDB_TYPE_DUCKDB_TYPE_HUGEINT,
+            T::UInt8 => DUCKDB_TYPE_DUCKDB_TYPE_UTINYINT,
+            T::UInt16 => DUCKDB_TYPE_DUCKDB_TYPE_USMALLINT,
+            T::UInt32 => DUCKDB_TYPE_DUCKDB_TYPE_UINTEGER,
+            T::UInt64 => DUCKDB_TYPE_DUCKDB_TYPE_UBIGINT,
+            T::UInt128 => DUCKDB_TYPE_DUCKDB_TYPE_UHUGEINT,
+            T::Float32 => DUCKDB_TYPE_DUCKDB_TYPE_FLOAT,
+            T::Float64 => DUCKDB_TYPE_DUCKDB_TYPE_DOUBLE,
+            T::Varchar | T::Json => DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR,
+            T::Blob => DUCKDB_TYPE_DUCKDB_TYPE_BLOB,
+            T::Date => DUCKDB_TYPE_DUCKDB_TYPE_DATE,
+            T::Time => DUCKDB_TYPE_DUCKDB_TYPE_TIME,
+            T::Timestamp => DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP,
+            T::TimestampWithTimezone => DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_TZ,
+            T::Interval => DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL,
+            T::Uuid => DUCKDB_TYPE_DUCKDB_TYPE_UUID,
+            T::Decimal(..) | T::Array(..) | T::List(..) | T::Map(..) | T::Struct(..) => {
+                DUCKDB_TYPE_DUCKDB_TYPE_INVALID
+            }
+        };
+        let destroy = |mut logical_type| unsafe { duckdb_destroy_logical_type(&mut logical_type) };
+        let logical_type = unsafe {
+            match data_type {
+                // DuckDB uses DECIMAL(18, 3) when the precision is not specified
+                T::Decimal(0, 0) => duckdb_create_decimal_type(18, 3),
+                T::Decimal(width, scale) => duckdb_create_decimal_type(*width, *scale),
+                T::Array(child, len) => {
+                    duckdb_create_array_type(*Self::create_logical_type(child)?, *len as idx_t)
+                }
+                T::List(child) => duckdb_create_list_type(*Self::create_logical_type(child)?),
+                T::Map(key, value) => duckdb_create_map_type(
+                    *Self::create_logical_type(key)?,
+                    *Self::create_logical_type(value)?,
+                ),
+                T::Struct(fields) => {
+                    let names = fields
+                        .iter()
+                        .map(|(name, _)| make_cstring(name))
+                        .collect::<Result<Vec<_>>>()?;
+                    let types = fields
+                        .iter()
+                        .map(|(_, data_type)| Self::create_logical_type(data_type))
+                        .collect::<Result<Vec<_>>>()?;
+                    let mut names = names.iter().map(|v| v.as_ptr()).collect::<Vec<_>>();
+                    let mut types = types.iter().map(|v| **v).collect::<Vec<_>>();
+                    duckdb_create_struct_type(
+                        types.as_mut_ptr(),
+                        names.as_mut_ptr(),
+                        fields.len() as idx_t,
+                    )
+                }
+                _ => duckdb_create_logical_type(type_id),
+            }
+        };
+        let logical_type = CBox::new(logical_type, destroy);
+        if matches!(data_type, T::Json) {
+            unsafe { duckdb_logical_type_set_alias(*logical_type, c"JSON".as_ptr()) };
+        }
+        Ok(logical_type)
+    }
+
+    /// Reads the type described by a DuckDB logical type.
+    fn read_logical_type(logical_type: duckdb_logical_type) -> Result<DuckDBType> {
+        type T = DuckDBType;
+        let destroy = |mut logical_type| unsafe { duckdb_destroy_logical_type(&mut logical_type) };
+        let type_id = unsafe { duckdb_get_type_id(logical_type) };
+        let result = match type_id {
+            DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN => T::Boolean,
+            DUCKDB_TYPE_DUCKDB_TYPE_TINYINT => T::Int8,
+            DUCKDB_TYPE_DUCKDB_TYPE_SMALLINT => T::Int16,
+            DUCKDB_TYPE_DUCKDB_TYPE_INTEGER => T::Int32,
+            DUCKDB_TYPE_DUCKDB_TYPE_BIGINT => T::Int64,
+            DUCKDB_TYPE_DUCKDB_TYPE_HUGEINT => T::Int128,
+            DUCKDB_TYPE_DUCKDB_TYPE_UTINYINT => T::UInt8,
+            DUCKDB_TYPE_DUCKDB_TYPE_USMALLINT => T::UInt16,
+            DUCKDB_TYPE_DUCKDB_TYPE_UINTEGER => T::UInt32,
+            DUCKDB_TYPE_DUCKDB_TYPE_UBIGINT => T::UInt64,
+            DUCKDB_TYPE_DUCKDB_TYPE_UHUGEINT => T::UInt128,
+            DUCKDB_TYPE_DUCKDB_TYPE_FLOAT => T::Float32,
+            DUCKDB_TYPE_DUCKDB_TYPE_DOUBLE => T::Float64,
+            DUCKDB_TYPE_DUCKDB_TYPE_DECIMAL => unsafe {
+                T::Decimal(
+                    duckdb_decimal_width(logical_type),
+                    duckdb_decimal_scale(logical_type),
+                )
+            },
+            DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR if has_json_alias(logical_type) => T::Json,
+            DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR => T::Varchar,
+            DUCKDB_TYPE_DUCKDB_TYPE_BLOB => T::Blob,
+            DUCKDB_TYPE_DUCKDB_TYPE_DATE => T::Date,
+            DUCKDB_TYPE_DUCKDB_TYPE_TIME => T::Time,
+            DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP
+            | DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_S
+            | DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_MS
+            | DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_NS => T::Timestamp,
+            DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_TZ => T::TimestampWithTimezone,
+            DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL => T::Interval,
+            DUCKDB_TYPE_DUCKDB_TYPE_ARRAY => unsafe {
+                let child = CBox::new(duckdb_array_type_child_type(logical_type), destroy);
+                T::Array(
+                    Self::read_logical_type(*child)?.into(),
+                    duckdb_array_type_array_size(logical_type) as usize,
+                )
+            },
+            DUCKDB_TYPE_DUCKDB_TYPE_LIST => unsafe {
+                let child = CBox::new(duckdb_list_type_child_type(logical_type), destroy);
+                T::List(Self::read_logical_type(*child)?.into())
+            },
+            DUCKDB_TYPE_DUCKDB_TYPE_MAP => unsafe {
+                let key = CBox::new(duckdb_map_type_key_type(logical_type), destroy);
+                let value = CBox::new(duckdb_map_type_value_type(logical_type), destroy);
+                T::Map(
+                    Self::read_logical_type(*key)?.into(),
+                    Self::read_logical_type(*value)?.into(),
+                )
+            },
+            DUCKDB_TYPE_DUCKDB_TYPE_STRUCT => unsafe {
+                let fields = (0..duckdb_struct_type_child_count(logical_type)).map(|i| {
+                    let name = CBox::new(duckdb_struct_type_child_name(logical_type, i), |v| {
+                        duckdb_free(v.cast())
+                    });
+                    let name = CStr::from_ptr(*name).to_string_lossy().into_owned();
+                    let child = CBox::new(duckdb_struct_type_child_type(logical_type, i), destroy);
+                    Ok((name, Self::read_logical_type(*child)?))
+                });
+                T::Struct(fields.collect::<Result<_>>()?)
+            },
+            DUCKDB_TYPE_DUCKDB_TYPE_SQLNULL => T::Null,
+            _ => {
+                return Err(
+                    DuckDBError::new(format!("Unsupported DuckDB type {}", type_id)).into(),
+                );
             }
         };
         Ok(result)
     }
 
-    pub fn new(field: DuckDBField) -> Self {
+    /// DuckDB logical type described by this type information.
+    pub(crate) fn logical_type(&self) -> Result<CBox<duckdb_logical_type>> {
+        Self::create_logical_type(&self.data_type)
+    }
+
+    /// Type information of a DuckDB logical type, like the one of a column.
+    pub(crate) fn from_logical_type(logical_type: duckdb_logical_type) -> Result<Self> {
+        Ok(Self::new(Self::read_logical_type(logical_type)?))
+    }
+
+    pub(crate) fn new(data_type: DuckDBType) -> Self {
         Self {
-            type_name: Self::type_name(&field).unwrap_or("".into()),
-            field,
+            type_name: Self::type_name(&data_type),
+            children: data_type
+                .children()
+                .into_iter()
+                .map(|child| Self::new(child.clone()))
+                .collect(),
+            data_type,
         }
     }
 }
 
+/// Types are equal when they have the same structure, the name is only used for display.
 impl PartialEq for DuckdbDBTypeInfo {
     fn eq(&self, other: &Self) -> bool {
-        self.type_name == other.type_name
+        self.data_type == other.data_type
     }
 }
 
+impl Eq for DuckdbDBTypeInfo {}
+
 impl Display for DuckdbDBTypeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.pad(self.name())
@@ -121,37 +400,7 @@ impl Display for DuckdbDBTypeInfo {
 
 impl TypeInfo for DuckdbDBTypeInfo {
     fn is_null(&self) -> bool {
-        type F = DuckDBField;
-        matches!(
-            self.field,
-            F::Null
-                | F::Boolean(None, ..)
-                | F::Int8(None, ..)
-                | F::Int16(None, ..)
-                | F::Int32(None, ..)
-                | F::Int64(None, ..)
-                | F::Int128(None, ..)
-                | F::UInt8(None, ..)
-                | F::UInt16(None, ..)
-                | F::UInt32(None, ..)
-                | F::UInt64(None, ..)
-                | F::UInt128(None, ..)
-                | F::Float32(None, ..)
-                | F::Float64(None, ..)
-                | F::Decimal(None, ..)
-                | F::Varchar(None, ..)
-                | F::Blob(None, ..)
-                | F::Date(None, ..)
-                | F::Time(None, ..)
-                | F::Timestamp(None, ..)
-                | F::TimestampWithTimezone(None, ..)
-                | F::Interval(None, ..)
-                | F::Uuid(None, ..)
-                | F::Json(None, ..)
-                | F::Array(None, ..)
-                | F::List(None, ..)
-                | F::Map(None, ..)
-        )
+        self.data_type == DuckDBType::Null
     }
 
     fn name(&self) -> &str {
@@ -162,29 +411,12 @@ impl TypeInfo for DuckdbDBTypeInfo {
     where
         Self: Sized,
     {
-        if self == other {
-            return true;
-        }
-
-        if let (
-            DuckDBField::Decimal(_, self_w, self_s),
-            DuckDBField::Decimal(_, other_w, other_s),
-        ) = (&self.field, &other.field)
-        {
-            return *self_w == 0 && *self_s == 0 || *other_w == 0 && *other_s == 0;
-        }
-        false
-    }
-}
-
-impl From<DuckDBField> for DuckdbDBTypeInfo {
-    fn from(field: DuckDBField) -> Self {
-        Self::new(field)
+        self.data_type.accepts(&other.data_type)
     }
 }
 
-impl From<DuckdbDBTypeInfo> for DuckDBField {
-    fn from(value: DuckdbDBTypeInfo) -> Self {
-        value.field
+impl From<DuckDBType> for DuckdbDBTypeInfo {
+    fn from(data_type: DuckDBType) -> Self {
+        Self::new(data_type)
     }
 }
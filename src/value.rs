@@ -1,15 +1,56 @@
-use crate::{database::DuckDB, type_info::DuckdbDBTypeInfo};
+use crate::{database::DuckDB, interval::Interval, type_info::DuckdbDBTypeInfo};
+use rust_decimal::Decimal;
 use sqlx_core::{
-    type_info::TypeInfo,
+    types::time::{Date, OffsetDateTime, PrimitiveDateTime, Time},
     value::{Value, ValueRef},
 };
 use std::borrow::Cow;
+use uuid::Uuid;
 
+/// Value read from DuckDB, its type is described by the [DuckdbDBTypeInfo] stored next to it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) enum DuckDBValueData {
+    #[default]
+    Null,
+    Boolean(bool),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Int128(i128),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    UInt128(u128),
+    Float32(f32),
+    Float64(f64),
+    Decimal(Decimal),
+    Varchar(String),
+    Blob(Box<[u8]>),
+    Date(Date),
+    Time(Time),
+    Timestamp(PrimitiveDateTime),
+    TimestampWithTimezone(OffsetDateTime),
+    Interval(Interval),
+    Uuid(Uuid),
+    /// Text of the JSON document.
+    Json(String),
+    Array(Box<[DuckDBValueData]>),
+    List(Vec<DuckDBValueData>),
+    Map(Vec<(DuckDBValueData, DuckDBValueData)>),
+    Struct(Vec<DuckDBValueData>),
+}
+
+#[derive(Debug, Clone)]
 pub struct DuckDBValue {
+    pub(crate) data: DuckDBValueData,
     pub(crate) type_info: DuckdbDBTypeInfo,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct DuckDBValueRef<'a> {
+    pub(crate) data: &'a DuckDBValueData,
     pub(crate) type_info: &'a DuckdbDBTypeInfo,
 }
 
@@ -17,7 +58,10 @@ impl Value for DuckDBValue {
     type Database = DuckDB;
 
     fn as_ref(&self) -> DuckDBValueRef<'_> {
-        todo!()
+        DuckDBValueRef {
+            data: &self.data,
+            type_info: &self.type_info,
+        }
     }
 
     fn type_info(&self) -> Cow<'_, DuckdbDBTypeInfo> {
@@ -25,7 +69,7 @@ impl Value for DuckDBValue {
     }
 
     fn is_null(&self) -> bool {
-        self.type_info.is_null()
+        self.data == DuckDBValueData::Null
     }
 }
 
@@ -34,15 +78,16 @@ impl<'a> ValueRef<'a> for DuckDBValueRef<'a> {
 
     fn to_owned(&self) -> DuckDBValue {
         DuckDBValue {
+            data: self.data.clone(),
             type_info: self.type_info.clone(),
         }
     }
 
     fn type_info(&self) -> Cow<'a, DuckdbDBTypeInfo> {
-        Cow::Borrowed(&self.type_info)
+        Cow::Borrowed(self.type_info)
     }
 
     fn is_null(&self) -> bool {
-        self.type_info.is_null()
+        *self.data == DuckDBValueData::Null
     }
 }
@@ -0,0 +1,167 @@
+mod tests {
+    use futures::TryStreamExt;
+    use sqlx::{
+        AnyConnection, Either,
+        any::{AnyPoolOptions, install_drivers},
+        prelude::*,
+    };
+
+    fn install() {
+        // The drivers can be installed once per process, the tests share them
+        let _ = install_drivers(&[sqlx_duckdb::any::DRIVER]);
+    }
+
+    #[tokio::test]
+    async fn query_values() {
+        install();
+        let mut connection = AnyConnection::connect("duckdb://%3Amemory%3A?instance_reuse=never")
+            .await
+            .unwrap();
+        assert_eq!(connection.backend_name(), "DuckDB");
+        let row = sqlx::query(
+            "SELECT true AS b, 1::TINYINT AS t, 2::SMALLINT AS s, 3::UINTEGER AS u, 4::BIGINT AS i, \
+            1.5::FLOAT AS f, 2.5::DOUBLE AS d, 'text' AS v, '\\xAA\\xBB'::BLOB AS x, NULL::INTEGER AS n",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert!(row.get::<bool, _>("b"));
+        assert_eq!(row.get::<i16, _>("t"), 1);
+        assert_eq!(row.get::<i16, _>("s"), 2);
+        assert_eq!(row.get::<i64, _>("u"), 3);
+        assert_eq!(row.get::<i64, _>("i"), 4);
+        assert_eq!(row.get::<f32, _>("f"), 1.5);
+        assert_eq!(row.get::<f64, _>("d"), 2.5);
+        assert_eq!(row.get::<String, _>("v"), "text");
+        assert_eq!(row.get::<Vec<u8>, _>("x"), [0xAA, 0xBB]);
+        assert_eq!(row.get::<Option<i32>, _>("n"), None);
+
+        let error = sqlx::query("SELECT '2025-01-01'::DATE")
+            .fetch_one(&mut connection)
+            .await
+            .map(|_| ())
+            .expect_err("DATE is not supported by the Any driver")
+            .to_string();
+        assert!(error.contains("DATE"), "{error}");
+
+        // Reported as errors rather than panics
+        let error = connection
+            .prepare("SELECT 1")
+            .await
+            .map(|_| ())
+            .expect_err("Prepared statements are not supported")
+            .to_string();
+        assert!(error.contains("prepared statements"), "{error}");
+        let error = connection
+            .describe("SELECT 1")
+            .await
+            .map(|_| ())
+            .expect_err("Describe is not supported")
+            .to_string();
+        assert!(error.contains("describing queries"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn bound_arguments() {
+        install();
+        let mut connection = AnyConnection::connect("duckdb://%3Amemory%3A?instance_reuse=never")
+            .await
+            .unwrap();
+        let (sum, text, blob, null) =
+            sqlx::query_as::<_, (i32, String, Vec<u8>, Option<i64>)>("SELECT ? + 1, ?, ?, ?")
+                .bind(41_i32)
+                .bind("text")
+                .bind(vec![0xAA_u8, 0xBB])
+                .bind(None::<i64>)
+                .fetch_one(&mut connection)
+                .await
+                .unwrap();
+        assert_eq!(sum, 42);
+        assert_eq!(text, "text");
+        assert_eq!(blob, [0xAA, 0xBB]);
+        assert_eq!(null, None);
+
+        sqlx::query("CREATE TABLE items (v DOUBLE)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO items VALUES (?), (?)")
+            .bind(1.5_f64)
+            .bind(2.5_f64)
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        let (sum,) = sqlx::query_as::<_, (f64,)>("SELECT sum(v) FROM items WHERE v > ?")
+            .bind(1.0_f64)
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(sum, 4.0);
+    }
+
+    #[tokio::test]
+    async fn execute_and_transactions() {
+        install();
+        let mut connection = AnyConnection::connect("duckdb://%3Amemory%3A?instance_reuse=never")
+            .await
+            .unwrap();
+        let results = sqlx::raw_sql(
+            "CREATE TABLE items (v INTEGER); INSERT INTO items VALUES (1), (2); SELECT v FROM items",
+        )
+        .fetch_many(&mut connection)
+        .map_ok(|v| match v {
+            Either::Left(result) => Either::Left(result.rows_affected()),
+            Either::Right(row) => Either::Right(row.get::<i32, _>(0)),
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(
+            results,
+            [
+                Either::Left(0),
+                Either::Left(2),
+                Either::Right(1),
+                Either::Right(2),
+                Either::Left(0),
+            ]
+        );
+
+        let mut tx = Connection::begin(&mut connection).await.unwrap();
+        sqlx::query("INSERT INTO items VALUES (3)")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        let (count,) = sqlx::query_as::<_, (i64,)>("SELECT count(*) FROM items")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn pool() {
+        install();
+        let pool = AnyPoolOptions::new()
+            .max_connections(2)
+            .test_before_acquire(false)
+            .connect("duckdb://%3Amemory%3Aany_pool")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE items (v VARCHAR)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO items VALUES ('a')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let (value,) = sqlx::query_as::<_, (String,)>("SELECT v FROM items")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(value, "a");
+        pool.close().await;
+    }
+}
@@ -0,0 +1,76 @@
+mod tests {
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{
+        connection::DuckDBConnection, database::DuckDBDatabase, options::DuckDBConnectOptions,
+    };
+    use std::{fs, path::Path};
+
+    #[tokio::test]
+    async fn close() {
+        const DB_PATH: &str = "target/debug/database_close.db";
+        if Path::new(DB_PATH).exists() {
+            fs::remove_file(DB_PATH).unwrap();
+        }
+        let options = DuckDBConnectOptions::new_in_memory().filename(DB_PATH);
+        let database = DuckDBDatabase::open(&options).await.unwrap();
+        let mut first = database.connect().await.unwrap();
+        let second = DuckDBConnection::connect(&format!("duckdb://{DB_PATH}"))
+            .await
+            .unwrap();
+        assert_eq!(database.connection_count(), 2);
+        assert_eq!(second.database().connection_count(), 2);
+        sqlx::query("CREATE TABLE closed (a INTEGER)")
+            .execute(&mut first)
+            .await
+            .unwrap();
+        second.close().await.unwrap();
+        assert_eq!(database.connection_count(), 1);
+
+        let error = database
+            .clone()
+            .close()
+            .await
+            .expect_err("The database is still used by a connection")
+            .to_string();
+        assert!(error.contains("still used by 1 connections"), "{error}");
+        first.close().await.unwrap();
+        database.close().await.unwrap();
+
+        // The file is no longer used and can be recreated
+        fs::remove_file(DB_PATH).unwrap();
+        let mut connection = DuckDBConnection::connect(&format!("duckdb://{DB_PATH}"))
+            .await
+            .unwrap();
+        sqlx::query("SELECT * FROM closed")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("Expected a new database");
+    }
+
+    #[tokio::test]
+    async fn evict() {
+        const URL: &str = "duckdb://:memory:database_evict";
+        let mut connection = DuckDBConnection::connect(URL).await.unwrap();
+        sqlx::query("CREATE TABLE evicted (a INTEGER)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        // The cache keeps the database open
+        let mut connection = DuckDBConnection::connect(URL).await.unwrap();
+        sqlx::query("SELECT * FROM evicted")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        assert!(DuckDBDatabase::evict(":memory:database_evict"));
+        assert!(!DuckDBDatabase::evict(":memory:database_evict"));
+        let mut connection = DuckDBConnection::connect(URL).await.unwrap();
+        sqlx::query("SELECT * FROM evicted")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("Expected a new database");
+    }
+}
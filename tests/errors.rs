@@ -0,0 +1,152 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use sqlx::error::ErrorKind;
+    use sqlx_duckdb::{
+        connection::DuckDBConnection,
+        database::DuckDB,
+        error::{DuckDBError, DuckDBErrorType},
+    };
+
+    async fn expect_error(connection: &mut DuckDBConnection, query: &str) -> sqlx::Error {
+        sqlx::query(query)
+            .execute(connection)
+            .await
+            .expect_err("The query was expected to fail")
+    }
+
+    fn error_type(error: &sqlx::Error) -> Option<DuckDBErrorType> {
+        error
+            .as_database_error()
+            .expect("Expected a database error")
+            .downcast_ref::<DuckDBError>()
+            .error_type()
+    }
+
+    #[tokio::test]
+    async fn parser_error() {
+        let mut connection = connect("parser_error").await;
+        let error = sqlx::query("SELEC 1")
+            .execute(&mut connection)
+            .await
+            .expect_err("The query was expected to fail");
+        let message = error.as_database_error().unwrap().message();
+        assert!(message.starts_with("Parser Error"), "{message}");
+        assert!(message.contains("LINE 1: SELEC 1"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn binder_error() {
+        let mut connection = connect("binder_error").await;
+        let error = sqlx::query("SELECT missing FROM range(3)")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The query was expected to fail");
+        let message = error.as_database_error().unwrap().message();
+        assert!(message.starts_with("Binder Error"), "{message}");
+        assert!(message.contains("missing"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn execution_error() {
+        let mut connection = connect("execution_error").await;
+        sqlx::query("CREATE TABLE unique_a (a INTEGER PRIMARY KEY)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO unique_a VALUES (1)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        let error = sqlx::query("INSERT INTO unique_a VALUES (1)")
+            .execute(&mut connection)
+            .await
+            .expect_err("The query was expected to fail");
+        let message = error.as_database_error().unwrap().message();
+        assert!(message.starts_with("Constraint Error"), "{message}");
+
+        // The connection is still usable after an error
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM unique_a")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn streaming_error() {
+        let mut connection = connect("streaming_error").await;
+        let error = sqlx::query("SELECT (range || 'x')::INTEGER FROM range(10)")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The query was expected to fail");
+        let message = error.as_database_error().unwrap().message();
+        assert!(message.starts_with("Conversion Error"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn error_types() {
+        let mut connection = connect("error_types").await;
+        let error = expect_error(&mut connection, "SELEC 1").await;
+        assert_eq!(error_type(&error), Some(DuckDBErrorType::Parser));
+        let error = expect_error(&mut connection, "SELECT * FROM missing").await;
+        assert_eq!(error_type(&error), Some(DuckDBErrorType::Catalog));
+        let error = expect_error(&mut connection, "SELECT missing FROM range(3)").await;
+        assert_eq!(error_type(&error), Some(DuckDBErrorType::Binder));
+        let error = expect_error(
+            &mut connection,
+            "SELECT (range || 'x')::INTEGER FROM range(3)",
+        )
+        .await;
+        assert_eq!(error_type(&error), Some(DuckDBErrorType::Conversion));
+        assert_eq!(error.as_database_error().unwrap().kind(), ErrorKind::Other);
+    }
+
+    #[tokio::test]
+    async fn constraint_violations() {
+        let mut connection = connect("constraint_violations").await;
+        for query in [
+            "CREATE TABLE parent (id INTEGER PRIMARY KEY, code VARCHAR UNIQUE)",
+            r#"
+            CREATE TABLE child (
+                id INTEGER NOT NULL,
+                parent INTEGER REFERENCES parent(id),
+                amount INTEGER CHECK (amount > 0)
+            )
+            "#,
+            "INSERT INTO parent VALUES (1, 'a')",
+        ] {
+            sqlx::query(query).execute(&mut connection).await.unwrap();
+        }
+
+        for (query, kind) in [
+            (
+                "INSERT INTO parent VALUES (1, 'b')",
+                ErrorKind::UniqueViolation,
+            ),
+            (
+                "INSERT INTO parent VALUES (2, 'a')",
+                ErrorKind::UniqueViolation,
+            ),
+            (
+                "INSERT INTO child VALUES (1, 2, 1)",
+                ErrorKind::ForeignKeyViolation,
+            ),
+            (
+                "INSERT INTO child VALUES (NULL, 1, 1)",
+                ErrorKind::NotNullViolation,
+            ),
+            (
+                "INSERT INTO child VALUES (1, 1, 0)",
+                ErrorKind::CheckViolation,
+            ),
+        ] {
+            let error = expect_error(&mut connection, query).await;
+            assert_eq!(error_type(&error), Some(DuckDBErrorType::Constraint));
+            assert_eq!(error.as_database_error().unwrap().kind(), kind, "{error}");
+        }
+        let error = expect_error(&mut connection, "INSERT INTO parent VALUES (3, 'a')").await;
+        assert!(error.as_database_error().unwrap().is_unique_violation());
+    }
+}
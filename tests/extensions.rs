@@ -0,0 +1,89 @@
+mod tests {
+    use sqlx::{ConnectOptions, prelude::*};
+    use sqlx_duckdb::{
+        connection::DuckDBConnection, database::DuckDB, options::DuckDBConnectOptions,
+    };
+
+    #[tokio::test]
+    async fn builtin_extensions() {
+        let mut connection = DuckDBConnection::connect(
+            "duckdb://:memory:extensions_builtin?extensions=core_functions",
+        )
+        .await
+        .expect("Could not open the database");
+        let (loaded,) = sqlx::query_as::<DuckDB, (i64,)>(
+            r#"SELECT count(*)
+                FROM duckdb_extensions()
+                WHERE loaded AND extension_name = 'core_functions'"#,
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(loaded, 1);
+    }
+
+    #[tokio::test]
+    async fn offline_settings() {
+        let directory = std::env::temp_dir().join("sqlx_duckdb_extensions");
+        let options = DuckDBConnectOptions::new_in_memory()
+            .filename(":memory:extensions_offline")
+            .allow_unsigned_extensions(true)
+            .extension_directory(&directory);
+        let mut connection = options.connect().await.unwrap();
+        let (allow_unsigned, extension_directory) = sqlx::query_as::<DuckDB, (bool, String)>(
+            r#"SELECT
+                    current_setting('allow_unsigned_extensions'),
+                    current_setting('extension_directory')"#,
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert!(allow_unsigned);
+        assert_eq!(extension_directory, directory.to_string_lossy());
+    }
+
+    #[tokio::test]
+    async fn missing_extensions() {
+        let options = DuckDBConnectOptions::new_in_memory()
+            .filename(":memory:extensions_missing")
+            .extension_directory(std::env::temp_dir().join("sqlx_duckdb_no_extensions"))
+            .setting("custom_extension_repository", "/nonexistent/repository");
+
+        let error = options
+            .clone()
+            .extension("not_an_extension")
+            .connect()
+            .await
+            .expect_err("The extension was expected to be missing")
+            .to_string();
+        assert!(
+            error.contains("loading the DuckDB extension `not_an_extension`"),
+            "{error}"
+        );
+
+        let error = options
+            .clone()
+            .extension_from_path("/nonexistent/missing.duckdb_extension")
+            .connect()
+            .await
+            .expect_err("The extension file was expected to be missing")
+            .to_string();
+        assert!(
+            error.contains("`/nonexistent/missing.duckdb_extension`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn extensions_url() {
+        let options = DuckDBConnectOptions::new(
+            "duckdb://:memory:?extensions=json,+icu,,/opt/spatial.duckdb_extension,json",
+        )
+        .unwrap()
+        .extension("fts");
+        assert_eq!(
+            options.to_url_lossy().as_str(),
+            "duckdb://%3Amemory%3A?extensions=json%2Cicu%2C%2Fopt%2Fspatial.duckdb_extension%2Cfts"
+        );
+    }
+}
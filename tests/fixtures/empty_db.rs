@@ -1,15 +1,14 @@
-use std::{fs, path::PathBuf, str::FromStr, sync::Mutex};
+use std::{fs, mem::ManuallyDrop, path::PathBuf, str::FromStr};
 
 use sqlx_core::connection::Connection;
-use sqlx_duckdb::connection::DuckDBConnection;
+use sqlx_duckdb::{connection::DuckDBConnection, database::DuckDBDatabase};
 
 const CREATE_PATH: fn(&str) -> PathBuf =
     |name| PathBuf::from_str(format!("target/debug/{}.db", name).as_str()).unwrap();
-static MUTEX: Mutex<()> = Mutex::new(());
 
 pub struct EmptyDB {
     path: PathBuf,
-    pub connection: DuckDBConnection,
+    pub connection: ManuallyDrop<DuckDBConnection>,
 }
 
 impl EmptyDB {
@@ -33,12 +32,18 @@ impl EmptyDB {
         )
         .await
         .expect("Could not connect to the database");
-        Self { path, connection }
+        Self {
+            path,
+            connection: ManuallyDrop::new(connection),
+        }
     }
 }
 
 impl Drop for EmptyDB {
     fn drop(&mut self) {
-        //fs::remove_file(&self.path).expect("Failed to remove the database file");
+        // The database file is closed once both the connection and the cache release it
+        unsafe { ManuallyDrop::drop(&mut self.connection) };
+        DuckDBDatabase::evict(&self.path.to_string_lossy());
+        fs::remove_file(&self.path).expect("Failed to remove the database file");
     }
 }
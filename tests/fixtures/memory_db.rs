@@ -0,0 +1,10 @@
+use sqlx_core::connection::Connection;
+use sqlx_duckdb::connection::DuckDBConnection;
+
+/// Connects to the in-memory database `name`, each test uses its own name so that the tests
+/// running in parallel don't share their tables.
+pub async fn connect(name: &str) -> DuckDBConnection {
+    DuckDBConnection::connect(&format!("duckdb://:memory:{name}"))
+        .await
+        .expect("Could not open the database")
+}
@@ -1 +1,5 @@
+// Each test binary uses some of the fixtures only
+#![allow(dead_code)]
+
 pub mod empty_db;
+pub mod memory_db;
@@ -0,0 +1,354 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use sqlx::{Type, error::BoxDynError};
+    use sqlx_duckdb::{
+        database::DuckDB,
+        function::{DuckDBAggregate, DuckDBChunkColumn},
+    };
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn scalar_function() {
+        let mut connection = connect("scalar_function").await;
+        connection
+            .register_scalar_function(
+                "decode_id",
+                &[<String as Type<DuckDB>>::type_info()],
+                |args| {
+                    args[0]
+                        .values::<String>()
+                        .map(|id| Ok(id?.trim_start_matches("ID-").parse::<i64>()?))
+                        .collect()
+                },
+            )
+            .unwrap();
+        connection
+            .register_scalar_function(
+                "weighted",
+                &[
+                    <i64 as Type<DuckDB>>::type_info(),
+                    <f64 as Type<DuckDB>>::type_info(),
+                ],
+                |args| {
+                    (0..args[0].len())
+                        .map(|row| Ok(args[0].get::<i64>(row)? as f64 * args[1].get::<f64>(row)?))
+                        .collect()
+                },
+            )
+            .unwrap();
+
+        // Spans multiple data chunks
+        let (sum, weighted) = sqlx::query_as::<DuckDB, (f64, f64)>(
+            r#"SELECT
+                sum(decode_id('ID-' || range::VARCHAR))::DOUBLE,
+                max(weighted(range, 0.5))
+            FROM range(10000)"#,
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(sum, 49995000.0);
+        assert_eq!(weighted, 4999.5);
+    }
+
+    #[tokio::test]
+    async fn null_values() {
+        let mut connection = connect("null_values").await;
+        connection
+            .register_scalar_function(
+                "double_or_null",
+                &[<i32 as Type<DuckDB>>::type_info()],
+                |args| {
+                    args[0]
+                        .values::<Option<i32>>()
+                        .map(|v| Ok(v?.filter(|v| *v != 0).map(|v| v * 2)))
+                        .collect()
+                },
+            )
+            .unwrap();
+        let values = sqlx::query_as::<DuckDB, (Option<i32>,)>(
+            "SELECT double_or_null(v) FROM (VALUES (1), (NULL), (0), (4)) t(v)",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(v,)| v)
+        .collect::<Vec<_>>();
+        assert_eq!(values, [Some(2), None, None, Some(8)]);
+    }
+
+    #[tokio::test]
+    async fn without_parameters() {
+        let mut connection = connect("without_parameters").await;
+        connection
+            .register_scalar_function("answer", &[], |args| {
+                assert!(args.is_empty());
+                Ok(vec![42_i64])
+            })
+            .unwrap();
+        let (count, sum) = sqlx::query_as::<DuckDB, (i64, i64)>(
+            "SELECT count(answer()), sum(answer())::BIGINT FROM range(5000)",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(count, 5000);
+        assert_eq!(sum, 210000);
+    }
+
+    #[tokio::test]
+    async fn function_errors() {
+        let mut connection = connect("function_errors").await;
+        connection
+            .register_scalar_function("failing", &[<i64 as Type<DuckDB>>::type_info()], |_| {
+                Err::<Vec<i64>, _>("custom failure".into())
+            })
+            .unwrap();
+        connection
+            .register_scalar_function(
+                "panicking",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                |_| -> Result<Vec<i64>, _> { panic!("custom panic") },
+            )
+            .unwrap();
+        connection
+            .register_scalar_function(
+                "wrong_length",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                |_| Ok(vec![1_i64]),
+            )
+            .unwrap();
+
+        for (query, expected) in [
+            ("SELECT failing(range) FROM range(3)", "custom failure"),
+            ("SELECT panicking(range) FROM range(3)", "custom panic"),
+            (
+                "SELECT wrong_length(range) FROM range(3)",
+                "1 values for 3 rows",
+            ),
+        ] {
+            let error = sqlx::query(query)
+                .fetch_all(&mut connection)
+                .await
+                .expect_err("The function was expected to fail")
+                .to_string();
+            assert!(error.contains(expected), "{error}");
+        }
+
+        // The connection is still usable
+        let (value,) = sqlx::query_as::<DuckDB, (i32,)>("SELECT 42")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    struct CountDistinct;
+
+    impl DuckDBAggregate for CountDistinct {
+        type State = HashSet<i64>;
+        type Output = i64;
+
+        fn init(&self) -> Self::State {
+            HashSet::new()
+        }
+
+        fn update(
+            &self,
+            state: &mut Self::State,
+            args: &[DuckDBChunkColumn],
+            row: usize,
+        ) -> Result<(), BoxDynError> {
+            if let Some(value) = args[0].get::<Option<i64>>(row)? {
+                state.insert(value);
+            }
+            Ok(())
+        }
+
+        fn combine(
+            &self,
+            target: &mut Self::State,
+            source: &Self::State,
+        ) -> Result<(), BoxDynError> {
+            target.extend(source);
+            Ok(())
+        }
+
+        fn finalize(&self, state: &Self::State) -> Result<Self::Output, BoxDynError> {
+            Ok(state.len() as i64)
+        }
+    }
+
+    struct FailingSum;
+
+    impl DuckDBAggregate for FailingSum {
+        type State = i64;
+        type Output = i64;
+
+        fn init(&self) -> Self::State {
+            0
+        }
+
+        fn update(
+            &self,
+            state: &mut Self::State,
+            args: &[DuckDBChunkColumn],
+            row: usize,
+        ) -> Result<(), BoxDynError> {
+            *state += args[0].get::<i64>(row)?;
+            Ok(())
+        }
+
+        fn combine(
+            &self,
+            target: &mut Self::State,
+            source: &Self::State,
+        ) -> Result<(), BoxDynError> {
+            *target += source;
+            Ok(())
+        }
+
+        fn finalize(&self, state: &Self::State) -> Result<Self::Output, BoxDynError> {
+            if *state > 100 {
+                return Err(format!("sum {state} is too large").into());
+            }
+            Ok(*state)
+        }
+    }
+
+    struct FailingInit;
+
+    impl DuckDBAggregate for FailingInit {
+        type State = i64;
+        type Output = i64;
+
+        fn init(&self) -> Self::State {
+            panic!("no state for this group")
+        }
+
+        fn update(
+            &self,
+            _state: &mut Self::State,
+            _args: &[DuckDBChunkColumn],
+            _row: usize,
+        ) -> Result<(), BoxDynError> {
+            Ok(())
+        }
+
+        fn combine(
+            &self,
+            _target: &mut Self::State,
+            _source: &Self::State,
+        ) -> Result<(), BoxDynError> {
+            Ok(())
+        }
+
+        fn finalize(&self, state: &Self::State) -> Result<Self::Output, BoxDynError> {
+            Ok(*state)
+        }
+    }
+
+    #[tokio::test]
+    async fn aggregate_function() {
+        let mut connection = connect("aggregate_function").await;
+        connection
+            .register_aggregate_function(
+                "count_distinct",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                CountDistinct,
+            )
+            .unwrap();
+
+        // Spans multiple data chunks and groups
+        let groups = sqlx::query_as::<DuckDB, (i64, i64)>(
+            r#"SELECT range % 3 AS g, count_distinct(range // 7)
+            FROM range(10000)
+            GROUP BY g
+            ORDER BY g"#,
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+        let expected = sqlx::query_as::<DuckDB, (i64, i64)>(
+            r#"SELECT range % 3 AS g, count(DISTINCT range // 7)
+            FROM range(10000)
+            GROUP BY g
+            ORDER BY g"#,
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(groups, expected);
+
+        let (empty, with_nulls) = sqlx::query_as::<DuckDB, (i64, i64)>(
+            r#"SELECT
+                (SELECT count_distinct(range) FROM range(0)),
+                (SELECT count_distinct(v) FROM (VALUES (1), (NULL), (1), (2)) t(v))"#,
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!((empty, with_nulls), (0, 2));
+
+        // Window functions combine partial states
+        let running = sqlx::query_as::<DuckDB, (i64,)>(
+            r#"SELECT count_distinct(range % 4) OVER (ORDER BY range)
+            FROM range(6)
+            ORDER BY range"#,
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(v,)| v)
+        .collect::<Vec<_>>();
+        assert_eq!(running, [1, 2, 3, 4, 4, 4]);
+    }
+
+    #[tokio::test]
+    async fn aggregate_errors() {
+        let mut connection = connect("aggregate_errors").await;
+        connection
+            .register_aggregate_function(
+                "failing_sum",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                FailingSum,
+            )
+            .unwrap();
+        let (sum,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT failing_sum(range) FROM range(10)")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(sum, 45);
+        let error = sqlx::query("SELECT failing_sum(range) FROM range(20)")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The aggregate was expected to fail")
+            .to_string();
+        assert!(error.contains("sum 190 is too large"), "{error}");
+
+        // The states that could not be created are never used
+        connection
+            .register_aggregate_function(
+                "failing_init",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                FailingInit,
+            )
+            .unwrap();
+        let error =
+            sqlx::query("SELECT range % 2 AS g, failing_init(range) FROM range(10) GROUP BY g")
+                .fetch_all(&mut connection)
+                .await
+                .expect_err("The aggregate was expected to fail")
+                .to_string();
+        assert!(error.contains("no state for this group"), "{error}");
+        let (value,) = sqlx::query_as::<DuckDB, (i32,)>("SELECT 42")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+}
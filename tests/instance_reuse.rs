@@ -0,0 +1,97 @@
+mod tests {
+    use sqlx::{ConnectOptions, prelude::*};
+    use sqlx_duckdb::{
+        connection::DuckDBConnection,
+        database::DuckDB,
+        options::{DuckDBConnectOptions, InstanceReuse},
+    };
+    use std::{fs, path::Path};
+
+    async fn threads(connection: &mut DuckDBConnection) -> i64 {
+        let (threads,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT current_setting('threads')")
+            .fetch_one(connection)
+            .await
+            .unwrap();
+        threads
+    }
+
+    #[tokio::test]
+    async fn strict() {
+        let _first = DuckDBConnection::connect("duckdb://:memory:reuse_strict?threads=2")
+            .await
+            .expect("Could not open the database");
+        let error = DuckDBConnection::connect("duckdb://:memory:reuse_strict?threads=3")
+            .await
+            .expect_err("The configuration was expected to conflict")
+            .to_string();
+        assert!(
+            error.contains(r#"`threads` is "2" instead of "3""#),
+            "{error}"
+        );
+        DuckDBConnection::connect("duckdb://:memory:reuse_strict?threads=2")
+            .await
+            .expect("Could not open the database with the same configuration");
+
+        const DB_PATH: &str = "target/debug/instance_reuse.db";
+        if Path::new(DB_PATH).exists() {
+            fs::remove_file(DB_PATH).unwrap();
+        }
+        let _writable = DuckDBConnection::connect(&format!("duckdb://{DB_PATH}?mode=rw"))
+            .await
+            .expect("Could not open the database");
+        let error = DuckDBConnection::connect(&format!("duckdb://{DB_PATH}?mode=ro"))
+            .await
+            .expect_err("The access mode was expected to conflict")
+            .to_string();
+        assert!(
+            error.contains(r#"`mode` is "rw" instead of "ro""#),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn lenient() {
+        let mut first = DuckDBConnection::connect("duckdb://:memory:reuse_lenient?threads=2")
+            .await
+            .expect("Could not open the database");
+        sqlx::query("CREATE TABLE shared (a INTEGER)")
+            .execute(&mut first)
+            .await
+            .unwrap();
+
+        let mut second = DuckDBConnectOptions::new("duckdb://:memory:reuse_lenient?threads=3")
+            .unwrap()
+            .instance_reuse(InstanceReuse::Lenient)
+            .connect()
+            .await
+            .expect("Could not reuse the database");
+        assert_eq!(threads(&mut second).await, 2);
+        sqlx::query("SELECT * FROM shared")
+            .fetch_all(&mut second)
+            .await
+            .expect("Expected the same database instance");
+    }
+
+    #[tokio::test]
+    async fn never() {
+        let options = DuckDBConnectOptions::new(
+            "duckdb://:memory:reuse_never?threads=2&instance_reuse=never",
+        )
+        .unwrap();
+        let mut first = options.connect().await.unwrap();
+        sqlx::query("CREATE TABLE private (a INTEGER)")
+            .execute(&mut first)
+            .await
+            .unwrap();
+
+        let mut second = options.clone().threads(3).connect().await.unwrap();
+        assert_eq!(threads(&mut first).await, 2);
+        assert_eq!(threads(&mut second).await, 3);
+        sqlx::query("SELECT * FROM private")
+            .fetch_all(&mut second)
+            .await
+            .expect_err("Expected a separate database instance");
+        first.close().await.unwrap();
+        second.close().await.unwrap();
+    }
+}
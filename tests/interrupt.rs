@@ -0,0 +1,76 @@
+mod tests {
+    use futures::{FutureExt, StreamExt};
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{connection::DuckDBConnection, database::DuckDB, error::DuckDBError};
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+        time::Duration,
+    };
+
+    const LONG_QUERY: &str = "SELECT sum(range) FROM range(10000000000)";
+
+    #[tokio::test]
+    async fn interrupt_handle() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:")
+            .await
+            .expect("Could not open the database");
+
+        let handle = connection.interrupt_handle();
+        let done = Arc::new(AtomicBool::new(false));
+        let interrupter = thread::spawn({
+            let done = done.clone();
+            move || {
+                // Keep interrupting because the query might not be started yet
+                while !done.load(Ordering::Relaxed) {
+                    handle.interrupt();
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+        });
+
+        let error = sqlx::query(LONG_QUERY)
+            .fetch_one(&mut connection)
+            .await
+            .expect_err("The query was expected to be interrupted");
+        done.store(true, Ordering::Relaxed);
+        interrupter.join().unwrap();
+        assert!(
+            error
+                .as_database_error()
+                .expect("Expected a database error")
+                .downcast_ref::<DuckDBError>()
+                .is_interrupted()
+        );
+
+        let (value,) = sqlx::query_as::<DuckDB, (i32,)>("SELECT 42")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[tokio::test]
+    async fn interrupt_on_drop() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:")
+            .await
+            .expect("Could not open the database");
+
+        {
+            let mut stream = sqlx::query(LONG_QUERY).fetch(&mut connection);
+            assert!(stream.next().now_or_never().is_none());
+            // Give the query the time to start
+            thread::sleep(Duration::from_millis(200));
+        }
+
+        // Would not complete if the previous query was still running
+        let (value,) = sqlx::query_as::<DuckDB, (i32,)>("SELECT 42")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+}
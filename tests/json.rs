@@ -0,0 +1,152 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use serde::{Deserialize, Serialize};
+    use sqlx::{
+        Column, TypeInfo,
+        prelude::*,
+        types::{Json, JsonValue},
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Payload {
+        id: i64,
+        tags: Vec<String>,
+        parent: Option<i64>,
+    }
+
+    #[tokio::test]
+    async fn json_column() {
+        let mut connection = connect("json_column").await;
+        sqlx::query("CREATE TABLE payloads (id INTEGER, payload JSON, text VARCHAR)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO payloads VALUES
+                (1, '{"id": 10, "tags": ["a", "b"], "parent": null}', '{"id": 11, "tags": []}'),
+                (2, NULL, NULL)"#,
+        )
+        .execute(&mut connection)
+        .await
+        .unwrap();
+        let rows = sqlx::query("SELECT payload, text FROM payloads ORDER BY id")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].columns()[0].type_info().name(), "JSON");
+        assert_eq!(rows[0].columns()[1].type_info().name(), "VARCHAR");
+
+        let expected = Payload {
+            id: 10,
+            tags: vec!["a".into(), "b".into()],
+            parent: None,
+        };
+        assert_eq!(rows[0].get::<Json<Payload>, _>("payload").0, expected);
+        let value = rows[0].get::<JsonValue, _>("payload");
+        assert_eq!(value["tags"][1], "b");
+        // The JSON document is also readable as a string
+        let text = rows[0].get::<String, _>("payload");
+        assert_eq!(serde_json::from_str::<JsonValue>(&text).unwrap(), value);
+
+        // A VARCHAR holding JSON
+        assert_eq!(
+            rows[0].get::<Json<Payload>, _>("text").0,
+            Payload {
+                id: 11,
+                tags: vec![],
+                parent: None,
+            }
+        );
+        assert_eq!(rows[0].get::<JsonValue, _>("text")["id"], 11);
+
+        assert!(rows[1].get::<Option<Json<Payload>>, _>("payload").is_none());
+        assert!(rows[1].get::<Option<JsonValue>, _>("payload").is_none());
+        assert!(rows[1].try_get::<JsonValue, _>("payload").is_err());
+    }
+
+    #[tokio::test]
+    async fn nested_json() {
+        let mut connection = connect("nested_json").await;
+        let row = sqlx::query(
+            r#"SELECT ['{"a": 1}'::JSON, NULL] AS list, json_object('k', 2) AS object, 3 AS int"#,
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        let list = row.get::<Vec<Option<JsonValue>>, _>("list");
+        assert_eq!(list, [Some(serde_json::json!({"a": 1})), None]);
+        assert_eq!(
+            row.get::<JsonValue, _>("object"),
+            serde_json::json!({"k": 2})
+        );
+        assert!(row.try_get::<JsonValue, _>("int").is_err());
+    }
+
+    #[tokio::test]
+    async fn text() {
+        let mut connection = connect("text").await;
+        // Read as it is stored, without reordering the keys or rounding the numbers
+        let document = r#"{"b": [1, 2], "a": 12345678901234567890123}"#;
+        let row = sqlx::query(&format!("SELECT '{document}'::JSON AS document"))
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("document"), document);
+        assert_eq!(row.get::<JsonValue, _>("document")["b"][1], 2);
+    }
+
+    #[tokio::test]
+    async fn encode() {
+        let mut connection = connect("encode").await;
+        sqlx::query("CREATE TABLE payloads (id INTEGER, payload JSON)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        let payload = Payload {
+            id: 1,
+            tags: vec!["x".into()],
+            parent: Some(0),
+        };
+        sqlx::query("INSERT INTO payloads VALUES (1, ?), (2, ?), (3, ?)")
+            .bind(Json(&payload))
+            .bind(serde_json::json!([1, 2]))
+            .bind(None::<JsonValue>)
+            .execute(&mut connection)
+            .await
+            .unwrap();
+
+        let rows = sqlx::query("SELECT payload FROM payloads ORDER BY id")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].get::<Json<Payload>, _>("payload").0, payload);
+        assert_eq!(
+            rows[1].get::<JsonValue, _>("payload"),
+            serde_json::json!([1, 2])
+        );
+        assert!(rows[2].get::<Option<JsonValue>, _>("payload").is_none());
+
+        // Each statement takes its own arguments
+        let rows = sqlx::query("SELECT ?::JSON AS a; SELECT ?::JSON AS b")
+            .bind(serde_json::json!("first"))
+            .bind(serde_json::json!("second"))
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].get::<JsonValue, _>("a"), "first");
+        assert_eq!(rows[1].get::<JsonValue, _>("b"), "second");
+
+        let error = sqlx::query("SELECT ?::JSON, ?::JSON")
+            .bind(serde_json::json!(1))
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("An argument is missing")
+            .to_string();
+        assert!(
+            error.contains("expects 2 arguments but 1 remain"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+mod tests {
+    use sqlx::{
+        migrate::{MigrateDatabase, Migrator},
+        prelude::*,
+    };
+    use sqlx_duckdb::{connection::DuckDBConnection, database::DuckDB};
+    use std::{fs, path::PathBuf};
+
+    /// Writes the migration scripts into a new directory.
+    fn migrations(name: &str, scripts: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("sqlx_duckdb_migrations_{name}"));
+        if directory.exists() {
+            fs::remove_dir_all(&directory).unwrap();
+        }
+        fs::create_dir_all(&directory).unwrap();
+        for (file, sql) in scripts {
+            fs::write(directory.join(file), sql).unwrap();
+        }
+        directory
+    }
+
+    #[tokio::test]
+    async fn migrate_database() {
+        const URL: &str = "duckdb://target/debug/migrate_database.db";
+        DuckDB::drop_database(URL).await.unwrap();
+        assert!(!DuckDB::database_exists(URL).await.unwrap());
+        DuckDB::create_database(URL).await.unwrap();
+        assert!(DuckDB::database_exists(URL).await.unwrap());
+        DuckDB::drop_database(URL).await.unwrap();
+        assert!(!DuckDB::database_exists(URL).await.unwrap());
+        assert!(DuckDB::database_exists("duckdb://:memory:").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn run_and_undo() {
+        const URL: &str = "duckdb://target/debug/migrate_run.db";
+        DuckDB::drop_database(URL).await.unwrap();
+        let directory = migrations(
+            "run",
+            &[
+                (
+                    "1_users.up.sql",
+                    "CREATE TABLE users (id INTEGER, name VARCHAR); INSERT INTO users VALUES (1, 'a');",
+                ),
+                ("1_users.down.sql", "DROP TABLE users;"),
+                (
+                    "2_it's_orders.up.sql",
+                    "CREATE TABLE orders (user_id INTEGER);",
+                ),
+                ("2_it's_orders.down.sql", "DROP TABLE orders;"),
+            ],
+        );
+        let migrator = Migrator::new(directory.as_path()).await.unwrap();
+        let mut connection = DuckDBConnection::connect(URL).await.unwrap();
+        migrator.run(&mut connection).await.unwrap();
+        // Already applied
+        migrator.run(&mut connection).await.unwrap();
+
+        let applied = sqlx::query_as::<DuckDB, (i64, String, bool)>(
+            "SELECT version, description, success FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(
+            applied,
+            [
+                (1, "users".to_string(), true),
+                (2, "it's orders".to_string(), true),
+            ]
+        );
+        let (users,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM users")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(users, 1);
+
+        migrator.undo(&mut connection, 1).await.unwrap();
+        sqlx::query("SELECT * FROM orders")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The orders table was expected to be dropped");
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM _sqlx_migrations")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        connection.close().await.unwrap();
+        DuckDB::drop_database(URL).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failing_migration() {
+        let directory = migrations(
+            "failing",
+            &[
+                ("1_ok.sql", "CREATE TABLE ok (v INTEGER);"),
+                (
+                    "2_failing.sql",
+                    "CREATE TABLE partial (v INTEGER); INSERT INTO missing VALUES (1);",
+                ),
+            ],
+        );
+        let migrator = Migrator::new(directory.as_path()).await.unwrap();
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?instance_reuse=never")
+            .await
+            .unwrap();
+        let error = migrator
+            .run(&mut connection)
+            .await
+            .expect_err("The second migration was expected to fail")
+            .to_string();
+        assert!(error.contains("missing"), "{error}");
+
+        // The failing migration was rolled back entirely
+        sqlx::query("SELECT * FROM partial")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The partial table was expected to be rolled back");
+        let versions = sqlx::query_as::<DuckDB, (i64,)>("SELECT version FROM _sqlx_migrations")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(versions, [(1,)]);
+    }
+
+    #[tokio::test]
+    async fn concurrent_migrations() {
+        const URL: &str = "duckdb://target/debug/migrate_concurrent.db";
+        DuckDB::drop_database(URL).await.unwrap();
+        let directory = migrations(
+            "concurrent",
+            &[
+                ("1_first.sql", "CREATE TABLE first (v INTEGER);"),
+                ("2_second.sql", "CREATE TABLE second (v INTEGER);"),
+            ],
+        );
+        let migrator = Migrator::new(directory.as_path()).await.unwrap();
+        let mut first = DuckDBConnection::connect(URL).await.unwrap();
+        let mut second = DuckDBConnection::connect(URL).await.unwrap();
+        let (a, b) = tokio::join!(migrator.run(&mut first), migrator.run(&mut second));
+        a.unwrap();
+        b.unwrap();
+
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM _sqlx_migrations")
+            .fetch_one(&mut first)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        first.close().await.unwrap();
+        second.close().await.unwrap();
+        DuckDB::drop_database(URL).await.unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use futures::TryStreamExt;
+    use sqlx::{Either, prelude::*};
+    use sqlx_duckdb::database::DuckDB;
+
+    #[tokio::test]
+    async fn execute_script() {
+        let mut connection = connect("execute_script").await;
+        let result = sqlx::query(
+            r#"
+            -- Creates the table
+            CREATE TABLE items (id INTEGER, name VARCHAR);
+            INSERT INTO items VALUES (1, 'a;b'), (2, 'c');
+            INSERT INTO items VALUES (3, 'd');
+            "#,
+        )
+        .execute(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(result.rows_affected(), 3);
+
+        let names = sqlx::query_as::<DuckDB, (String,)>("SELECT name FROM items ORDER BY id")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(names, [("a;b".into(),), ("c".into(),), ("d".into(),)]);
+
+        // Comments only
+        let result = sqlx::query("-- nothing to do")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(result.rows_affected(), 0);
+    }
+
+    #[tokio::test]
+    async fn fetch_many() {
+        let mut connection = connect("fetch_many").await;
+        let results = sqlx::raw_sql(
+            r#"CREATE TABLE numbers AS SELECT range AS v FROM range(3);
+            SELECT v FROM numbers ORDER BY v;
+            DELETE FROM numbers WHERE v > 0;
+            SELECT count(*) FROM numbers"#,
+        )
+        .fetch_many(&mut connection)
+        .map_ok(|v| match v {
+            Either::Left(result) => Either::Left(result.rows_affected()),
+            Either::Right(row) => Either::Right(row.get::<i64, _>(0)),
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(
+            results,
+            [
+                Either::Left(0),
+                Either::Right(0),
+                Either::Right(1),
+                Either::Right(2),
+                Either::Left(0),
+                Either::Left(2),
+                Either::Right(1),
+                Either::Left(0),
+            ]
+        );
+
+        // The first row follows the results of the statements preceding it
+        let (value,) = sqlx::query_as::<DuckDB, (i64,)>(
+            "CREATE TABLE other (v BIGINT); INSERT INTO other VALUES (7); SELECT v FROM other",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(value, 7);
+    }
+
+    #[tokio::test]
+    async fn failing_statement() {
+        let mut connection = connect("failing_statement").await;
+        let error = sqlx::query(
+            r#"CREATE TABLE partial (v INTEGER);
+            INSERT INTO partial VALUES (1);
+            INSERT INTO missing VALUES (2);
+            INSERT INTO partial VALUES (3);"#,
+        )
+        .execute(&mut connection)
+        .await
+        .expect_err("The third statement was expected to fail")
+        .to_string();
+        assert!(error.contains("missing"), "{error}");
+
+        // The statements preceding the failing one were executed, the following ones were not
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM partial")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let error = sqlx::query("SELECT 1; SELEC 2")
+            .execute(&mut connection)
+            .await
+            .expect_err("The query was expected not to parse")
+            .to_string();
+        assert!(error.contains("LINE 1"), "{error}");
+    }
+}
@@ -0,0 +1,173 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use rust_decimal::Decimal;
+    use sqlx::{prelude::*, types::time};
+    use sqlx_duckdb::interval::Interval;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn scalars() {
+        let mut connection = connect("scalars").await;
+        sqlx::query(
+            "CREATE TABLE items (
+                boolean BOOLEAN,
+                int8 TINYINT,
+                int64 BIGINT,
+                uint128 UHUGEINT,
+                float64 DOUBLE,
+                decimal DECIMAL(10, 2),
+                varchar VARCHAR,
+                blob BLOB,
+                date DATE,
+                time TIME,
+                timestamp TIMESTAMP,
+                timestamp_tz TIMESTAMPTZ,
+                interval INTERVAL,
+                list INTEGER[],
+                map MAP(VARCHAR, INTEGER),
+                struct STRUCT(a INTEGER, b VARCHAR)
+            )",
+        )
+        .execute(&mut connection)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO items VALUES
+                (NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL,
+                    NULL, NULL, NULL),
+                (true, 1, 2, 3, 4.5, 6.25, 'seven', 'eight', '2025-01-09', '10:11:12',
+                    '2025-01-13 14:15:16', '2025-01-17 18:19:20+00', INTERVAL 21 DAYS, [22],
+                    MAP {'twenty three': 23}, {'a': 24, 'b': 'twenty five'})",
+        )
+        .execute(&mut connection)
+        .await
+        .unwrap();
+        let rows = sqlx::query("SELECT * FROM items ORDER BY boolean NULLS FIRST")
+            .fetch_all(&mut connection)
+            .await
+            .unwrap();
+
+        macro_rules! check {
+            ($column:literal, $rust_type:ty, $value:expr) => {
+                assert_eq!(rows[0].get::<Option<$rust_type>, _>($column), None);
+                assert_eq!(rows[1].get::<Option<$rust_type>, _>($column), Some($value));
+                assert!(rows[0].try_get::<$rust_type, _>($column).is_err());
+            };
+        }
+        check!("boolean", bool, true);
+        check!("int8", i8, 1);
+        check!("int64", i64, 2);
+        check!("uint128", u128, 3);
+        check!("float64", f64, 4.5);
+        check!("decimal", Decimal, Decimal::new(625, 2));
+        check!("varchar", String, "seven".to_string());
+        check!("blob", Vec<u8>, b"eight".to_vec());
+        check!(
+            "date",
+            time::Date,
+            time::Date::from_calendar_date(2025, 1.try_into().unwrap(), 9).unwrap()
+        );
+        check!(
+            "time",
+            time::Time,
+            time::Time::from_hms(10, 11, 12).unwrap()
+        );
+        check!(
+            "timestamp",
+            time::PrimitiveDateTime,
+            time::PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2025, 1.try_into().unwrap(), 13).unwrap(),
+                time::Time::from_hms(14, 15, 16).unwrap(),
+            )
+        );
+        check!(
+            "timestamp_tz",
+            time::OffsetDateTime,
+            time::PrimitiveDateTime::new(
+                time::Date::from_calendar_date(2025, 1.try_into().unwrap(), 17).unwrap(),
+                time::Time::from_hms(18, 19, 20).unwrap(),
+            )
+            .assume_utc()
+        );
+        check!(
+            "interval",
+            Interval,
+            Interval::from_duration(&std::time::Duration::from_secs(21 * 24 * 60 * 60))
+        );
+        check!("list", Vec<i32>, vec![22]);
+        check!(
+            "map",
+            HashMap<String, i32>,
+            HashMap::from([("twenty three".to_string(), 23)])
+        );
+        check!("struct", (i32, String), (24, "twenty five".to_string()));
+    }
+
+    #[tokio::test]
+    async fn null_literal() {
+        let mut connection = connect("null_literal").await;
+        let row = sqlx::query("SELECT NULL AS value")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<Option<i32>, _>("value"), None);
+        assert_eq!(row.get::<Option<String>, _>("value"), None);
+        assert_eq!(row.get::<Option<Vec<i64>>, _>("value"), None);
+        assert!(row.try_get::<i32, _>("value").is_err());
+    }
+
+    #[tokio::test]
+    async fn nested() {
+        let mut connection = connect("nested").await;
+        let row = sqlx::query(
+            "SELECT [1, NULL, 3] AS list, \
+            [[1, NULL], NULL, []] AS lists, \
+            [NULL, 'b']::VARCHAR[2] AS array, \
+            MAP {'a': 1, 'b': NULL} AS map, \
+            {'a': NULL, 'b': 'two'} AS struct, \
+            [{'a': 1, 'b': NULL}, NULL] AS structs",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            row.get::<Vec<Option<i32>>, _>("list"),
+            [Some(1), None, Some(3)]
+        );
+        // The elements are widened like the columns
+        assert_eq!(
+            row.get::<Vec<Option<i64>>, _>("list"),
+            [Some(1), None, Some(3)]
+        );
+        assert!(row.try_get::<Vec<i32>, _>("list").is_err());
+        assert_eq!(
+            row.get::<Vec<Option<Vec<Option<i32>>>>, _>("lists"),
+            [Some(vec![Some(1), None]), None, Some(vec![])]
+        );
+        assert_eq!(
+            row.get::<Vec<Option<String>>, _>("array"),
+            [None, Some("b".to_string())]
+        );
+
+        let map = row.get::<HashMap<String, Option<i32>>, _>("map");
+        assert_eq!(
+            map,
+            HashMap::from([("a".to_string(), Some(1)), ("b".to_string(), None)])
+        );
+        assert!(row.try_get::<HashMap<String, i32>, _>("map").is_err());
+
+        assert_eq!(
+            row.get::<(Option<i32>, Option<String>), _>("struct"),
+            (None, Some("two".to_string()))
+        );
+        assert!(row.try_get::<(i32, String), _>("struct").is_err());
+        assert!(row.try_get::<(i32,), _>("struct").is_err());
+        assert_eq!(
+            row.get::<Vec<Option<(i32, Option<i32>)>>, _>("structs"),
+            [Some((1, None)), None]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+mod tests {
+    use sqlx::ConnectOptions;
+    use sqlx_duckdb::{
+        database::DuckDB,
+        options::{DefaultOrder, DuckDBConnectOptions},
+    };
+
+    #[tokio::test]
+    async fn builder() {
+        let options = DuckDBConnectOptions::new_in_memory()
+            .threads(3)
+            .threads(2)
+            .memory_limit(500_000_000)
+            .default_order(DefaultOrder::Descending);
+        let mut connection = options
+            .connect()
+            .await
+            .expect("Could not open the database");
+
+        let (threads, memory_limit, default_order) =
+            sqlx::query_as::<DuckDB, (i64, String, String)>(
+                r#"SELECT
+                    current_setting('threads'),
+                    current_setting('memory_limit'),
+                    current_setting('default_order')"#,
+            )
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(threads, 2);
+        assert_eq!(memory_limit, "476.8 MiB");
+        assert_eq!(default_order.to_lowercase(), "desc");
+    }
+
+    #[test]
+    fn to_url_lossy() {
+        let options = DuckDBConnectOptions::new_in_memory()
+            .row_buffer_size(16)
+            .setting("threads", "4");
+        let url = options.to_url_lossy();
+        assert_eq!(
+            url.as_str(),
+            "duckdb://%3Amemory%3A?row_buffer_size=16&threads=4"
+        );
+        let parsed = DuckDBConnectOptions::from_url(&url).unwrap();
+        assert_eq!(parsed.to_url_lossy(), url);
+
+        let options = DuckDBConnectOptions::new_in_memory()
+            .filename("target/debug/some file?.db")
+            .read_only(true)
+            .track_progress(true)
+            .temp_directory("/tmp/duck db")
+            .max_temp_directory_size(1024);
+        let url = options.to_url_lossy();
+        assert_eq!(
+            url.as_str(),
+            "duckdb://target/debug/some%20file%3F.db?mode=ro&track_progress=true\
+                &temp_directory=%2Ftmp%2Fduck+db&max_temp_directory_size=1024B"
+        );
+        let parsed = DuckDBConnectOptions::from_url(&url).unwrap();
+        assert_eq!(parsed.to_url_lossy(), url);
+
+        // Characters reserved in urls are escaped
+        let options = DuckDBConnectOptions::new_in_memory().filename("data[1]^|@{x}'s.db");
+        let url = options.to_url_lossy();
+        assert_eq!(url.as_str(), "duckdb://data%5B1%5D%5E%7C%40%7Bx%7D%27s.db");
+        let parsed = DuckDBConnectOptions::from_url(&url).unwrap();
+        assert_eq!(parsed.to_url_lossy(), url);
+    }
+
+    #[test]
+    fn available_settings() {
+        let settings = DuckDBConnectOptions::available_settings();
+        let threads = settings
+            .iter()
+            .find(|s| s.name == "threads")
+            .expect("Expected the `threads` setting");
+        assert!(!threads.description.is_empty());
+        assert!(settings.iter().any(|s| s.name == "memory_limit"));
+    }
+
+    #[tokio::test]
+    async fn unknown_setting() {
+        let error = DuckDBConnectOptions::new("duckdb://:memory:?memory_limt=1GB")
+            .expect_err("The setting was expected to be rejected")
+            .to_string();
+        assert!(error.contains("`memory_limt`"), "{error}");
+        assert!(error.contains("did you mean `memory_limit`"), "{error}");
+        assert!(error.contains("memory"), "{error}");
+
+        let error = DuckDBConnectOptions::new("duckdb://:memory:?definitely_not_a_setting=1")
+            .expect_err("The setting was expected to be rejected")
+            .to_string();
+        assert!(!error.contains("did you mean"), "{error}");
+
+        let error = DuckDBConnectOptions::new_in_memory()
+            .setting("thread", "2")
+            .connect()
+            .await
+            .expect_err("The setting was expected to be rejected")
+            .to_string();
+        assert!(error.contains("did you mean `threads`"), "{error}");
+
+        // Names are case insensitive
+        DuckDBConnectOptions::new("duckdb://:memory:?THREADS=2").unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_setting_value() {
+        let error = DuckDBConnectOptions::new("duckdb://:memory:?threads=many")
+            .unwrap()
+            .connect()
+            .await
+            .expect_err("The value was expected to be rejected")
+            .to_string();
+        let threads = DuckDBConnectOptions::available_settings()
+            .iter()
+            .find(|s| s.name == "threads")
+            .unwrap();
+        assert!(error.contains("`threads` to \"many\""), "{error}");
+        assert!(error.contains(&threads.description), "{error}");
+    }
+
+    #[tokio::test]
+    async fn session_settings() {
+        let options = DuckDBConnectOptions::new_in_memory()
+            .filename(":memory:session_settings")
+            .session_setting("preserve_insertion_order", "true")
+            .session_setting("preserve_insertion_order", "false")
+            .session_setting("default_null_order", "nulls_first")
+            .after_connect("CREATE SCHEMA app; CREATE TABLE app.marker AS SELECT 'it''s' AS value;")
+            .after_connect("SET search_path = 'app,main'");
+        let mut connection = options
+            .connect()
+            .await
+            .expect("Could not open the database");
+
+        let (preserve_insertion_order, null_order, value) =
+            sqlx::query_as::<DuckDB, (String, String, String)>(
+                r#"SELECT
+                    current_setting('preserve_insertion_order')::VARCHAR,
+                    current_setting('default_null_order'),
+                    value
+                FROM marker"#,
+            )
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(preserve_insertion_order, "false");
+        assert_eq!(null_order.to_lowercase(), "nulls_first");
+        assert_eq!(value, "it's");
+
+        let error = DuckDBConnectOptions::new_in_memory()
+            .session_setting("null_ordr", "nulls_first")
+            .connect()
+            .await
+            .expect_err("The setting was expected to be rejected")
+            .to_string();
+        assert!(error.contains("did you mean `null_order`"), "{error}");
+
+        let error = DuckDBConnectOptions::new_in_memory()
+            .filename(":memory:session_settings")
+            .after_connect("SELECT * FROM missing")
+            .connect()
+            .await
+            .expect_err("The init SQL was expected to fail")
+            .to_string();
+        assert!(error.contains("missing"), "{error}");
+    }
+}
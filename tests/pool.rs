@@ -0,0 +1,96 @@
+mod tests {
+    use sqlx::prelude::*;
+    use sqlx_duckdb::database::{DuckDB, DuckDBPool, DuckDBPoolOptions};
+    use std::{fs, path::Path, time::Duration};
+
+    const TASKS: i64 = 32;
+    const INSERTS: i64 = 10;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn shared_file_database() {
+        const DB_PATH: &str = "target/debug/pool_shared.db";
+        if Path::new(DB_PATH).exists() {
+            fs::remove_file(DB_PATH).unwrap();
+        }
+        let pool: DuckDBPool = DuckDBPoolOptions::new()
+            .max_connections(4)
+            .connect(&format!("duckdb://{DB_PATH}"))
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE items (task BIGINT, v BIGINT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let tasks = (0..TASKS).map(|task| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                for v in 0..INSERTS {
+                    sqlx::query(&format!("INSERT INTO items VALUES ({task}, {v})"))
+                        .execute(&pool)
+                        .await
+                        .unwrap();
+                }
+                // Dropped without committing, the connection goes back to the pool rolled back
+                let mut tx = pool.begin().await.unwrap();
+                sqlx::query(&format!("INSERT INTO items VALUES ({task}, -1)"))
+                    .execute(&mut *tx)
+                    .await
+                    .unwrap();
+                drop(tx);
+                let (count,) = sqlx::query_as::<DuckDB, (i64,)>(&format!(
+                    "SELECT count(*) FROM items WHERE task = {task}"
+                ))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!(count, INSERTS);
+            })
+        });
+        for task in tasks.collect::<Vec<_>>() {
+            task.await.unwrap();
+        }
+
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM items")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, TASKS * INSERTS);
+        assert!(pool.size() <= 4);
+
+        let mut connection = pool.acquire().await.unwrap();
+        connection.ping().await.unwrap();
+        let database = connection.database().clone();
+        connection.close().await.unwrap();
+
+        // The connections go back to the pool in background tasks, a connection returned while
+        // the pool is closing could be left idle
+        let returned = async {
+            while pool.num_idle() < pool.size() as usize {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), returned)
+            .await
+            .expect("The connections were expected back in the pool");
+        // Closing the pool closes all its connections, the database is no longer used
+        pool.close().await;
+        assert_eq!(database.connection_count(), 0);
+        database.close().await.unwrap();
+        fs::remove_file(DB_PATH).unwrap();
+    }
+
+    #[tokio::test]
+    async fn close_hard() {
+        let pool = DuckDBPoolOptions::new()
+            .max_connections(1)
+            .connect("duckdb://:memory:?instance_reuse=never")
+            .await
+            .unwrap();
+        let connection = pool.acquire().await.unwrap();
+        let database = connection.database().clone();
+        connection.detach().close_hard().await.unwrap();
+        assert_eq!(database.connection_count(), 0);
+        pool.close().await;
+    }
+}
@@ -0,0 +1,79 @@
+mod tests {
+    use sqlx::prelude::*;
+    use sqlx_duckdb::connection::DuckDBConnection;
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const LONG_QUERY: &str = "SELECT sum(range) FROM range(10000000000)";
+
+    #[tokio::test]
+    async fn progress_handle() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?track_progress=true")
+            .await
+            .expect("Could not open the database");
+        let progress_handle = connection.progress_handle();
+        let interrupt_handle = connection.interrupt_handle();
+        assert_eq!(progress_handle.progress(), None);
+
+        let observer = thread::spawn(move || {
+            let start = Instant::now();
+            loop {
+                if let Some(progress) = progress_handle.progress() {
+                    interrupt_handle.interrupt();
+                    return (progress, progress_handle);
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(60),
+                    "No progress was reported"
+                );
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+        sqlx::query(LONG_QUERY)
+            .fetch_one(&mut connection)
+            .await
+            .expect_err("The query was expected to be interrupted");
+        let (progress, progress_handle) = observer.join().unwrap();
+        assert!((0.0..100.0).contains(&progress.percentage));
+        assert!(progress.rows_processed < progress.total_rows_to_process);
+        assert_eq!(progress_handle.progress(), None);
+
+        drop(connection);
+        assert_eq!(progress_handle.progress(), None);
+    }
+
+    #[tokio::test]
+    async fn progress_not_tracked() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:")
+            .await
+            .expect("Could not open the database");
+        let progress_handle = connection.progress_handle();
+        let interrupt_handle = connection.interrupt_handle();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let observer = thread::spawn({
+            let done = done.clone();
+            move || {
+                thread::sleep(Duration::from_millis(200));
+                let progress = progress_handle.progress();
+                // An interrupt arriving before the query starts is lost, it is repeated until
+                // the query ends
+                while !done.load(Ordering::Relaxed) {
+                    interrupt_handle.interrupt();
+                    thread::sleep(Duration::from_millis(50));
+                }
+                progress
+            }
+        });
+        let result = sqlx::query(LONG_QUERY).fetch_one(&mut connection).await;
+        done.store(true, Ordering::Relaxed);
+        result.expect_err("The query was expected to be interrupted");
+        assert_eq!(observer.join().unwrap(), None);
+    }
+}
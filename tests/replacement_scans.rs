@@ -0,0 +1,70 @@
+mod tests {
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{
+        connection::DuckDBConnection, database::DuckDB, replacement_scan::DuckDBReplacementScan,
+    };
+    use std::fs;
+
+    #[tokio::test]
+    async fn replacement_scan() {
+        let directory = std::env::temp_dir().join("sqlx_duckdb_replacement_scan");
+        fs::create_dir_all(&directory).unwrap();
+        let files = ["2024", "2025"].map(|year| {
+            let path = directory.join(format!("sales_{year}.csv"));
+            fs::write(&path, format!("year,amount\n{year},10\n{year},15\n")).unwrap();
+            path.to_string_lossy().into_owned()
+        });
+
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?instance_reuse=never")
+            .await
+            .unwrap();
+        connection.database().add_replacement_scan(move |table| {
+            Ok(match table {
+                "sales" => Some(DuckDBReplacementScan::new("read_csv").parameter(files.to_vec())),
+                "failing" => return Err("The catalog is not available".into()),
+                _ => table
+                    .strip_prefix("numbers_")
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|v| DuckDBReplacementScan::new("range").parameter(v)),
+            })
+        });
+
+        let (count, total) = sqlx::query_as::<DuckDB, (i64, i64)>(
+            "SELECT count(*), sum(amount)::BIGINT FROM sales WHERE year > 2024",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!((count, total), (2, 25));
+
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM numbers_1234")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 1234);
+
+        // Tables are still resolved first
+        sqlx::query("CREATE TABLE numbers_5 AS SELECT 42 AS v")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM numbers_5")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        for (query, expected) in [
+            ("SELECT * FROM failing", "The catalog is not available"),
+            ("SELECT * FROM unknown", "unknown does not exist"),
+        ] {
+            let error = sqlx::query(query)
+                .fetch_all(&mut connection)
+                .await
+                .expect_err("The table was expected to be missing")
+                .to_string();
+            assert!(error.contains(expected), "{error}");
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
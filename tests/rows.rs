@@ -0,0 +1,104 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use futures::TryStreamExt;
+    use sqlx::{Column, TypeInfo, Value, ValueRef, prelude::*};
+    use sqlx_duckdb::database::DuckDB;
+
+    #[tokio::test]
+    async fn columns() {
+        let mut connection = connect("columns").await;
+        let rows = sqlx::query(
+            "SELECT range AS id, 'name ' || range AS name, NULL::DECIMAL(10, 2) AS price \
+            FROM range(3000)",
+        )
+        .fetch(&mut connection)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        // The rows span several chunks
+        assert_eq!(rows.len(), 3000);
+        for (i, row) in rows.iter().enumerate() {
+            let columns = row.columns();
+            assert_eq!(columns.len(), 3);
+            assert_eq!(columns[1].name(), "name");
+            assert_eq!(columns[1].ordinal(), 1);
+            assert_eq!(columns[2].type_info().name(), "DECIMAL(10, 2)");
+            assert_eq!(row.get::<i64, _>("id"), i as i64);
+            assert_eq!(row.get::<String, _>("name"), format!("name {i}"));
+            assert_eq!(row.get::<Option<rust_decimal::Decimal>, _>("price"), None);
+        }
+
+        let error = rows[0]
+            .try_get::<i64, _>("missing")
+            .expect_err("The column does not exist")
+            .to_string();
+        assert!(error.contains("id, name, price"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn duplicate_names() {
+        let mut connection = connect("duplicate_names").await;
+        let row = sqlx::query("SELECT 1 AS v, 2 AS v")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        // The first column with the name is the one found
+        assert_eq!(row.get::<i32, _>("v"), 1);
+        assert_eq!(row.get::<i32, _>(1), 2);
+
+        // Each statement of a query has its own columns
+        let rows = sqlx::raw_sql("SELECT 1 AS a; SELECT 'b' AS b")
+            .fetch(&mut connection)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rows[0].get::<i32, _>("a"), 1);
+        assert_eq!(rows[1].get::<String, _>("b"), "b");
+        let (value,) = sqlx::query_as::<DuckDB, (String,)>("SELECT 'c' AS b")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(value, "c");
+    }
+
+    #[tokio::test]
+    async fn values() {
+        let mut connection = connect("values").await;
+        let rows = sqlx::query(
+            "SELECT 1 AS id, 12.5::DECIMAL(10, 2) AS price UNION ALL SELECT 2, NULL ORDER BY id",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+
+        let value = ValueRef::to_owned(&rows[0].try_get_raw("id").unwrap());
+        assert!(!value.is_null());
+        assert_eq!(value.type_info().name(), "INTEGER");
+        assert_eq!(value.as_ref().type_info(), value.type_info());
+        assert_eq!(ValueRef::to_owned(&value.as_ref()).decode::<i32>(), 1);
+        assert_eq!(value.try_decode::<i32>().unwrap(), 1);
+
+        // The type describes the column, it is the same for NULL values
+        let price = rows[1].try_get_raw("price").unwrap();
+        assert!(price.is_null());
+        assert_eq!(price.type_info().name(), "DECIMAL(10, 2)");
+        assert_eq!(
+            rows[0].try_get_raw("price").unwrap().type_info(),
+            price.type_info()
+        );
+        assert!(
+            <rust_decimal::Decimal as Type<DuckDB>>::type_info()
+                .type_compatible(&price.type_info())
+        );
+        assert_ne!(
+            <i32 as Type<DuckDB>>::type_info(),
+            <i64 as Type<DuckDB>>::type_info()
+        );
+        assert_eq!(
+            rows[0].columns()[0].type_info(),
+            &<i32 as Type<DuckDB>>::type_info()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+mod tests {
+    use sqlx::{
+        pool::PoolConnection,
+        prelude::*,
+        testing::{TestArgs, TestSupport},
+    };
+    use sqlx_duckdb::database::{DuckDB, DuckDBDatabase, DuckDBPool};
+
+    async fn users(pool: &DuckDBPool) -> Vec<(i32, String)> {
+        sqlx::query_as("SELECT id, name FROM users ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "tests/migrations", fixtures("users"))]
+    async fn migrations_and_fixtures(pool: DuckDBPool) {
+        assert_eq!(
+            users(&pool).await,
+            [(1, "alice".to_string()), (2, "bob".to_string())]
+        );
+    }
+
+    // Both tests insert the same row, they would conflict if the database was shared
+    #[sqlx::test(migrations = "tests/migrations")]
+    async fn isolated_first(pool: DuckDBPool) {
+        sqlx::query("INSERT INTO users VALUES (1, 'first')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(users(&pool).await, [(1, "first".to_string())]);
+    }
+
+    #[sqlx::test(migrations = "tests/migrations")]
+    async fn isolated_second(pool: DuckDBPool) {
+        sqlx::query("INSERT INTO users VALUES (1, 'second')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(users(&pool).await, [(1, "second".to_string())]);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn connection(mut connection: PoolConnection<DuckDB>) {
+        let (value,) = sqlx::query_as::<DuckDB, (i32,)>("SELECT 42")
+            .fetch_one(&mut *connection)
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[tokio::test]
+    async fn cleanup() {
+        let context = DuckDB::test_context(&TestArgs::new("sqlx_test::cleanup"))
+            .await
+            .unwrap();
+        let mut connection = context.connect_opts.connect().await.unwrap();
+        sqlx::query("CREATE TABLE items (v INTEGER)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        DuckDB::cleanup_test(&context.db_name).await.unwrap();
+        assert!(!DuckDBDatabase::evict(&context.db_name));
+    }
+}
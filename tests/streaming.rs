@@ -0,0 +1,48 @@
+mod tests {
+    use futures::TryStreamExt;
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{connection::DuckDBConnection, database::DuckDB};
+
+    #[tokio::test]
+    async fn bounded_stream() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?row_buffer_size=16")
+            .await
+            .expect("Could not open the database");
+
+        let rows = sqlx::query_as::<DuckDB, (i64,)>("SELECT * FROM range(1000)")
+            .fetch(&mut connection)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rows, (0..1000).map(|v| (v,)).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn dropped_stream() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?row_buffer_size=1")
+            .await
+            .expect("Could not open the database");
+
+        {
+            let mut stream = sqlx::query_as::<DuckDB, (i64,)>("SELECT * FROM range(100000000)")
+                .fetch(&mut connection);
+            for i in 0..10 {
+                let (value,) = stream.try_next().await.unwrap().unwrap();
+                assert_eq!(value, i);
+            }
+        }
+
+        let (count,) = sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM range(10)")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(count, 10);
+    }
+
+    #[tokio::test]
+    async fn invalid_row_buffer_size() {
+        DuckDBConnection::connect("duckdb://:memory:?row_buffer_size=many")
+            .await
+            .expect_err("Connection succeeded but it was expected to fail");
+    }
+}
@@ -0,0 +1,244 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use sqlx::{Type, error::BoxDynError};
+    use sqlx_duckdb::{
+        database::DuckDB,
+        table_function::{DuckDBBindInfo, DuckDBInitInfo, DuckDBOutputChunk, DuckDBTableFunction},
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn table_iterator() {
+        let mut connection = connect("table_iterator").await;
+        let users = Arc::new(
+            (0..5000_i64)
+                .map(|id| (id, format!("user{id}"), (id % 2 == 0).then_some(id as f64)))
+                .collect::<Vec<_>>(),
+        );
+        let source = users.clone();
+        connection
+            .register_table_iterator("users", &["id", "name", "score"], move || {
+                source.as_ref().clone()
+            })
+            .unwrap();
+        sqlx::query("CREATE TABLE orders AS SELECT range * 7 AS user_id FROM range(100)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+
+        // Spans multiple data chunks and can be scanned again
+        for _ in 0..2 {
+            let (count, max_id) =
+                sqlx::query_as::<DuckDB, (i64, i64)>("SELECT count(*), max(id) FROM users()")
+                    .fetch_one(&mut connection)
+                    .await
+                    .unwrap();
+            assert_eq!((count, max_id), (5000, 4999));
+        }
+        let rows = sqlx::query_as::<DuckDB, (String, Option<f64>)>(
+            r#"SELECT name, score
+            FROM orders JOIN users() users ON orders.user_id = users.id
+            WHERE user_id < 15
+            ORDER BY user_id"#,
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("user0".to_string(), Some(0.0)),
+                ("user7".to_string(), None),
+                ("user14".to_string(), Some(14.0)),
+            ]
+        );
+    }
+
+    /// Produces `count` rows of the number and of its square, starting from `start`.
+    struct Squares {
+        projections: Arc<Mutex<Vec<Vec<usize>>>>,
+    }
+
+    struct SquaresScan {
+        next: i64,
+        end: i64,
+        projection: Vec<usize>,
+    }
+
+    impl DuckDBTableFunction for Squares {
+        type BindData = (i64, i64);
+        type InitData = SquaresScan;
+
+        fn bind(&self, info: &mut DuckDBBindInfo) -> Result<Self::BindData, BoxDynError> {
+            let count = info.parameter::<i64>(0)?;
+            if count < 0 {
+                return Err(format!("Invalid count {count}").into());
+            }
+            let start = info.named_parameter::<i64>("start")?.unwrap_or(0);
+            info.add_result_column("value", &<i64 as Type<DuckDB>>::type_info())?;
+            info.add_result_column("square", &<String as Type<DuckDB>>::type_info())?;
+            info.set_cardinality(count as usize, true);
+            Ok((start, start + count))
+        }
+
+        fn init(
+            &self,
+            bind_data: &Self::BindData,
+            info: &DuckDBInitInfo,
+        ) -> Result<Self::InitData, BoxDynError> {
+            let projection = info.projection();
+            self.projections.lock().unwrap().push(projection.clone());
+            Ok(SquaresScan {
+                next: bind_data.0,
+                end: bind_data.1,
+                projection,
+            })
+        }
+
+        fn produce(
+            &self,
+            _bind_data: &Self::BindData,
+            scan: &mut Self::InitData,
+            output: &mut DuckDBOutputChunk,
+        ) -> Result<(), BoxDynError> {
+            let rows = ((scan.end - scan.next) as usize).min(output.capacity());
+            for row in 0..rows {
+                let value = scan.next + row as i64;
+                for (column, index) in scan.projection.iter().enumerate() {
+                    let output = &mut output.columns()[column];
+                    match index {
+                        0 => output.set(row, value)?,
+                        _ => output.set(row, (value * value).to_string())?,
+                    }
+                }
+            }
+            scan.next += rows as i64;
+            output.set_len(rows)?;
+            Ok(())
+        }
+
+        fn projection_pushdown(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn table_function() {
+        let mut connection = connect("table_function").await;
+        let projections = Arc::new(Mutex::new(Vec::new()));
+        connection
+            .register_table_function(
+                "squares",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                &[("start", <i64 as Type<DuckDB>>::type_info())],
+                Squares {
+                    projections: projections.clone(),
+                },
+            )
+            .unwrap();
+
+        let rows = sqlx::query_as::<DuckDB, (String,)>(
+            "SELECT square FROM squares(3, start := 4) ORDER BY value",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(v,)| v)
+        .collect::<Vec<_>>();
+        assert_eq!(rows, ["16", "25", "36"]);
+        assert_eq!(projections.lock().unwrap().pop(), Some(vec![1, 0]));
+
+        let (sum,) =
+            sqlx::query_as::<DuckDB, (i64,)>("SELECT sum(value)::BIGINT FROM squares(10000)")
+                .fetch_one(&mut connection)
+                .await
+                .unwrap();
+        assert_eq!(sum, 49995000);
+        assert_eq!(projections.lock().unwrap().pop(), Some(vec![0]));
+
+        let error = sqlx::query("SELECT * FROM squares(-1)")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The bind was expected to fail")
+            .to_string();
+        assert!(error.contains("Invalid count -1"), "{error}");
+    }
+
+    /// Writes past the end of the output chunk, in the way selected by its parameter.
+    struct OutOfBounds;
+
+    impl DuckDBTableFunction for OutOfBounds {
+        type BindData = i64;
+        type InitData = ();
+
+        fn bind(&self, info: &mut DuckDBBindInfo) -> Result<Self::BindData, BoxDynError> {
+            info.add_result_column("value", &<i64 as Type<DuckDB>>::type_info())?;
+            Ok(info.parameter::<i64>(0)?)
+        }
+
+        fn init(
+            &self,
+            _bind_data: &Self::BindData,
+            _info: &DuckDBInitInfo,
+        ) -> Result<Self::InitData, BoxDynError> {
+            Ok(())
+        }
+
+        fn produce(
+            &self,
+            mode: &Self::BindData,
+            _scan: &mut Self::InitData,
+            output: &mut DuckDBOutputChunk,
+        ) -> Result<(), BoxDynError> {
+            let capacity = output.capacity();
+            match mode {
+                0 => output.columns()[0].set(capacity, 1_i64)?,
+                1 => output.columns()[0].set_null(capacity)?,
+                _ => output.set_len(capacity + 1)?,
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn out_of_bounds() {
+        let mut connection = connect("out_of_bounds").await;
+        connection
+            .register_table_function(
+                "out_of_bounds",
+                &[<i64 as Type<DuckDB>>::type_info()],
+                &[],
+                OutOfBounds,
+            )
+            .unwrap();
+        for (query, expected) in [
+            ("SELECT * FROM out_of_bounds(0)", "Cannot write row 2048"),
+            ("SELECT * FROM out_of_bounds(1)", "Cannot write row 2048"),
+            ("SELECT * FROM out_of_bounds(2)", "to 2049"),
+        ] {
+            let error = sqlx::query(query)
+                .fetch_all(&mut connection)
+                .await
+                .expect_err("The write was expected to fail")
+                .to_string();
+            assert!(error.contains(expected), "{error}");
+        }
+    }
+
+    #[tokio::test]
+    async fn table_iterator_errors() {
+        let mut connection = connect("table_iterator_errors").await;
+        connection
+            .register_table_iterator("mismatched", &["a"], || vec![(1_i32, 2_i32)])
+            .unwrap();
+        let error = sqlx::query("SELECT * FROM mismatched()")
+            .fetch_all(&mut connection)
+            .await
+            .expect_err("The names were expected to mismatch")
+            .to_string();
+        assert!(error.contains("2 columns but 1 names"), "{error}");
+    }
+}
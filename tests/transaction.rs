@@ -0,0 +1,54 @@
+mod tests {
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{connection::DuckDBConnection, database::DuckDB};
+
+    async fn count(connection: &mut DuckDBConnection) -> i64 {
+        sqlx::query_as::<DuckDB, (i64,)>("SELECT count(*) FROM items")
+            .fetch_one(connection)
+            .await
+            .unwrap()
+            .0
+    }
+
+    #[tokio::test]
+    async fn commit_and_rollback() {
+        let mut connection = DuckDBConnection::connect("duckdb://:memory:?instance_reuse=never")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE items (v INTEGER)")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+
+        let mut tx = Connection::begin(&mut connection).await.unwrap();
+        sqlx::query("INSERT INTO items VALUES (1)")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(count(&mut connection).await, 1);
+
+        let mut tx = Connection::begin(&mut connection).await.unwrap();
+        sqlx::query("INSERT INTO items VALUES (2)")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(count(&mut connection).await, 1);
+
+        // Dropping the transaction rolls it back
+        {
+            let mut tx = Connection::begin(&mut connection).await.unwrap();
+            sqlx::query("INSERT INTO items VALUES (3)")
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+        }
+        assert_eq!(count(&mut connection).await, 1);
+
+        let mut tx = Connection::begin(&mut connection).await.unwrap();
+        tx.begin()
+            .await
+            .expect_err("Nested transactions are not supported");
+    }
+}
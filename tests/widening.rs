@@ -0,0 +1,96 @@
+mod fixtures;
+
+mod tests {
+    use crate::fixtures::memory_db::connect;
+    use rust_decimal::Decimal;
+    use sqlx::prelude::*;
+    use sqlx_duckdb::{database::DuckDB, decode::Lossy};
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn lossless() {
+        let mut connection = connect("lossless").await;
+        let row = sqlx::query(
+            "SELECT 1::TINYINT AS tiny, 2::INTEGER AS int, 3::UINTEGER AS uint, \
+            4::UBIGINT AS ubig, 1.5::FLOAT AS float, 5::SMALLINT AS small",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(row.get::<i16, _>("tiny"), 1);
+        assert_eq!(row.get::<i64, _>("int"), 2);
+        assert_eq!(row.get::<i128, _>("int"), 2);
+        assert_eq!(row.get::<u64, _>("uint"), 3);
+        assert_eq!(row.get::<i64, _>("uint"), 3);
+        assert_eq!(row.get::<u128, _>("ubig"), 4);
+        assert_eq!(row.get::<i128, _>("ubig"), 4);
+        assert_eq!(row.get::<f64, _>("float"), 1.5);
+        assert_eq!(row.get::<f64, _>("int"), 2.0);
+        assert_eq!(row.get::<f32, _>("small"), 5.0);
+        assert_eq!(row.get::<Decimal, _>("int"), Decimal::from(2));
+        assert_eq!(row.get::<Option<i64>, _>("int"), Some(2));
+
+        // Narrowing, signed into unsigned and precision losses are refused
+        assert!(row.try_get::<i8, _>("int").is_err());
+        assert!(row.try_get::<u32, _>("int").is_err());
+        assert!(row.try_get::<i32, _>("uint").is_err());
+        assert!(row.try_get::<i64, _>("ubig").is_err());
+        assert!(row.try_get::<f32, _>("int").is_err());
+        assert!(row.try_get::<i64, _>("float").is_err());
+        assert!(row.try_get::<String, _>("int").is_err());
+    }
+
+    #[tokio::test]
+    async fn decimals() {
+        let mut connection = connect("decimals").await;
+        let row = sqlx::query(
+            "SELECT 12.5::DECIMAL(4, 1) AS small, 7::INTEGER AS int, 0.1::DECIMAL(3, 2) AS tenth",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert_eq!(
+            row.get::<Decimal, _>("small"),
+            Decimal::from_str("12.5").unwrap()
+        );
+        assert_eq!(row.get::<Decimal, _>("int"), Decimal::from(7));
+        // A decimal can't be read as a float without the lossy mode
+        assert!(row.try_get::<f64, _>("small").is_err());
+        assert_eq!(row.get::<Lossy<f64>, _>("small"), Lossy(12.5));
+        assert_eq!(row.get::<Lossy<f64>, _>("tenth"), Lossy(0.1));
+    }
+
+    #[tokio::test]
+    async fn lossy() {
+        let mut connection = connect("lossy").await;
+        // SUM of a BIGINT is a HUGEINT
+        let row = sqlx::query(
+            "SELECT SUM(v) AS total, 2.75::DOUBLE AS double, 1e300::DOUBLE AS huge, \
+            -1::INTEGER AS negative \
+            FROM (VALUES (1::BIGINT), (2::BIGINT)) t(v)",
+        )
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+        assert!(row.try_get::<i64, _>("total").is_err());
+        assert_eq!(row.get::<Lossy<i64>, _>("total"), Lossy(3));
+        assert_eq!(row.get::<Lossy<i32>, _>("double"), Lossy(2));
+        assert_eq!(row.get::<Lossy<f32>, _>("double"), Lossy(2.75));
+        assert_eq!(
+            row.get::<Lossy<Decimal>, _>("double"),
+            Lossy(Decimal::from_str("2.75").unwrap())
+        );
+        assert_eq!(row.get::<Option<Lossy<i8>>, _>("total"), Some(Lossy(3)));
+
+        // Values out of range are still refused
+        assert!(row.try_get::<Lossy<i64>, _>("huge").is_err());
+        assert!(row.try_get::<Lossy<f32>, _>("huge").is_err());
+        assert!(row.try_get::<Lossy<u8>, _>("negative").is_err());
+
+        let (total,) = sqlx::query_as::<DuckDB, (Lossy<u64>,)>("SELECT SUM(range) FROM range(10)")
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+        assert_eq!(total.0, 45);
+    }
+}